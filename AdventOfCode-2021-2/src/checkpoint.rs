@@ -0,0 +1,376 @@
+use crate::course::CourseDirection;
+use crate::navigation::{navigation_model_name, parse_navigation_model, NavigationState};
+use crate::observer::NavigationObserver;
+use std::fmt;
+use std::fs;
+use std::io;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+// Hashes a course one command at a time (64-bit FNV-1a), so the hash of a prefix can be carried in a checkpoint
+// and extended later. Unlike the standard library hashers its output is stable across builds.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CourseHasher(u64);
+
+impl Default for CourseHasher {
+    fn default() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+}
+
+impl CourseHasher {
+    pub fn update(&mut self, op: &CourseDirection) {
+        let (tag, n) = match op {
+            CourseDirection::Forward(n) => (0u8, n),
+            CourseDirection::Up(n) => (1u8, n),
+            CourseDirection::Down(n) => (2u8, n)
+        };
+        for byte in [tag].into_iter().chain(n.to_le_bytes()) {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+pub fn hash_course(course: &[CourseDirection]) -> u64 {
+    let mut hasher = CourseHasher::default();
+    for op in course.iter() {
+        hasher.update(op);
+    }
+    hasher.finish()
+}
+
+// Everything needed to carry on navigating part way through a course
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Checkpoint {
+    pub state: NavigationState,
+    // How many commands have been applied
+    pub command_index: usize,
+    pub with_aim: bool,
+    // Hash of the commands already applied, to check a resumed run is using the same course
+    pub prefix_hash: u64
+}
+
+// Checkpoints are written as one "<field> <value>" per line
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "command_index {}", self.command_index)?;
+        writeln!(f, "depth {}", self.state.depth)?;
+        writeln!(f, "horizontal {}", self.state.horizontal)?;
+        writeln!(f, "aim {}", self.state.aim)?;
+        writeln!(f, "model {}", navigation_model_name(self.with_aim))?;
+        writeln!(f, "prefix_hash {:016x}", self.prefix_hash)
+    }
+}
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    // When the checkpoint file can't be read or written
+    Io(io::Error),
+    // When a line isn't a field name and a value. First is line number, second is the full string
+    UnexpectedNumberOfTokens(usize, String),
+    // When a line names an unknown field. Second value is the field name.
+    InvalidField(usize, String),
+    // When a field's value can't be parsed. Second value is the unparseable value.
+    UnparseableValue(usize, String),
+    // When the checkpoint doesn't set a field. Value is the field name.
+    MissingField(&'static str),
+    // When the course is shorter than the checkpoint's progress. First is the checkpoint's index, second the course length.
+    CourseTooShort(usize, usize),
+    // When the course's first commands aren't the ones the checkpoint was taken on
+    PrefixMismatch,
+    // When the checkpoint was taken under a different navigation model. First is whether the checkpoint navigates
+    // with aim, second whether the run does.
+    ModelMismatch(bool, bool)
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(e) => write!(f, "{}", e),
+            CheckpointError::UnexpectedNumberOfTokens(line_num, line) => write!(f, "Line {}: expected a field and a value, got \"{}\"", line_num + 1, line),
+            CheckpointError::InvalidField(line_num, field) => write!(f, "Line {}: unknown field \"{}\"", line_num + 1, field),
+            CheckpointError::UnparseableValue(line_num, value) => write!(f, "Line {}: could not parse value \"{}\"", line_num + 1, value),
+            CheckpointError::MissingField(field) => write!(f, "Checkpoint has no {}", field),
+            CheckpointError::CourseTooShort(index, length) => write!(f, "Checkpoint is at command {} but the course only has {}", index, length),
+            CheckpointError::PrefixMismatch => write!(f, "Course does not match the one the checkpoint was taken on"),
+            CheckpointError::ModelMismatch(checkpoint, run) => write!(f, "Checkpoint was taken under the {} model but this run uses the {} model", navigation_model_name(*checkpoint), navigation_model_name(*run))
+        }
+    }
+}
+
+pub fn parse_checkpoint(input: &[String]) -> Result<Checkpoint, CheckpointError> {
+    let (mut command_index, mut depth, mut horizontal, mut aim, mut with_aim, mut prefix_hash) = (None, None, None, None, None, None);
+    for (line_num, line_str) in input.iter().enumerate() {
+        let split_string: Vec<&str> = line_str.split_whitespace().collect();
+        if split_string.len() != 2 {
+            return Err(CheckpointError::UnexpectedNumberOfTokens(line_num, line_str.to_string()));
+        }
+
+        let value = split_string[1];
+        let unparseable = || CheckpointError::UnparseableValue(line_num, value.to_string());
+        match split_string[0] {
+            "command_index" => command_index = Some(value.parse::<usize>().map_err(|_| unparseable())?),
+            "depth" => depth = Some(value.parse::<i32>().map_err(|_| unparseable())?),
+            "horizontal" => horizontal = Some(value.parse::<i32>().map_err(|_| unparseable())?),
+            "aim" => aim = Some(value.parse::<i32>().map_err(|_| unparseable())?),
+            "model" => with_aim = Some(parse_navigation_model(value).ok_or_else(unparseable)?),
+            "prefix_hash" => prefix_hash = Some(u64::from_str_radix(value, 16).map_err(|_| unparseable())?),
+            _ => return Err(CheckpointError::InvalidField(line_num, split_string[0].to_string()))
+        }
+    }
+
+    Ok(Checkpoint {
+        state: NavigationState {
+            depth: depth.ok_or(CheckpointError::MissingField("depth"))?,
+            horizontal: horizontal.ok_or(CheckpointError::MissingField("horizontal"))?,
+            aim: aim.ok_or(CheckpointError::MissingField("aim"))?
+        },
+        command_index: command_index.ok_or(CheckpointError::MissingField("command_index"))?,
+        with_aim: with_aim.ok_or(CheckpointError::MissingField("model"))?,
+        prefix_hash: prefix_hash.ok_or(CheckpointError::MissingField("prefix_hash"))?
+    })
+}
+
+// Writes to a temporary file first, so a crash part way through a write never leaves a broken checkpoint behind
+pub fn save_checkpoint(path: &str, checkpoint: &Checkpoint) -> Result<(), CheckpointError> {
+    let temporary_path = format!("{}.tmp", path);
+    fs::write(&temporary_path, checkpoint.to_string()).map_err(CheckpointError::Io)?;
+    fs::rename(&temporary_path, path).map_err(CheckpointError::Io)
+}
+
+pub fn load_checkpoint(path: &str) -> Result<Checkpoint, CheckpointError> {
+    let contents = fs::read_to_string(path).map_err(CheckpointError::Io)?;
+    let lines: Vec<String> = contents.lines().map(|l| l.trim().to_string()).collect();
+    parse_checkpoint(&lines)
+}
+
+// A navigation run that can be stopped and picked up again. It tracks how far through the course it is
+// alongside the position, so its progress can be captured as a checkpoint at any point.
+pub struct NavigationRun {
+    state: NavigationState,
+    command_index: usize,
+    with_aim: bool,
+    hasher: CourseHasher
+}
+
+impl NavigationRun {
+    pub fn new(with_aim: bool) -> Self {
+        Self {
+            state: NavigationState::default(),
+            command_index: 0,
+            with_aim,
+            hasher: CourseHasher::default()
+        }
+    }
+
+    // Picks up from a checkpoint, after checking it was taken under the same navigation model and the course starts
+    // with the commands it has covered
+    pub fn resume(checkpoint: &Checkpoint, course: &[CourseDirection], with_aim: bool) -> Result<Self, CheckpointError> {
+        if checkpoint.with_aim != with_aim {
+            return Err(CheckpointError::ModelMismatch(checkpoint.with_aim, with_aim));
+        }
+        let prefix = course
+            .get(..checkpoint.command_index)
+            .ok_or(CheckpointError::CourseTooShort(checkpoint.command_index, course.len()))?;
+        if hash_course(prefix) != checkpoint.prefix_hash {
+            return Err(CheckpointError::PrefixMismatch);
+        }
+
+        Ok(Self {
+            state: checkpoint.state,
+            command_index: checkpoint.command_index,
+            with_aim: checkpoint.with_aim,
+            hasher: CourseHasher(checkpoint.prefix_hash)
+        })
+    }
+
+    pub fn state(&self) -> NavigationState {
+        self.state
+    }
+
+    pub fn command_index(&self) -> usize {
+        self.command_index
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            state: self.state,
+            command_index: self.command_index,
+            with_aim: self.with_aim,
+            prefix_hash: self.hasher.finish()
+        }
+    }
+
+    pub fn step(&mut self, op: &CourseDirection) {
+        self.state.apply(op, self.with_aim);
+        self.hasher.update(op);
+        self.command_index += 1;
+    }
+
+    // Runs the rest of the course, handing a checkpoint to the callback after every interval commands.
+    // Stops early, returning its error, if the callback fails. The observer only sees the commands this call runs,
+    // so after resuming it starts part way through the course.
+    pub fn run<O: NavigationObserver, E, F: FnMut(&Checkpoint) -> Result<(), E>>(&mut self, course: &[CourseDirection], interval: usize, observer: &mut O, mut on_checkpoint: F) -> Result<NavigationState, E> {
+        for op in course[self.command_index.min(course.len())..].iter() {
+            observer.before_step(self.command_index, op, &self.state);
+            self.step(op);
+            observer.after_step(self.command_index - 1, op, &self.state);
+            if interval > 0 && self.command_index.is_multiple_of(interval) {
+                on_checkpoint(&self.checkpoint())?;
+            }
+        }
+
+        Ok(self.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::course::CourseDirection;
+    use crate::navigation::{navigate_from, NavigationState};
+    use crate::observer::{DepthAlert, DepthThresholdAlert};
+    use super::hash_course;
+    use super::load_checkpoint;
+    use super::parse_checkpoint;
+    use super::save_checkpoint;
+    use super::Checkpoint;
+    use super::CheckpointError;
+    use super::NavigationRun;
+
+    fn example_course() -> Vec<CourseDirection> {
+        vec![
+            CourseDirection::Forward(5),
+            CourseDirection::Down(5),
+            CourseDirection::Forward(8),
+            CourseDirection::Up(3),
+            CourseDirection::Down(8),
+            CourseDirection::Forward(2),
+        ]
+    }
+
+    #[test]
+    fn navigation_run_checkpoints_at_each_interval() {
+        // arrange
+        let course = example_course();
+        let mut run = NavigationRun::new(true);
+        let mut checkpoints = Vec::new();
+        // act
+        let result = run.run(&course, 2, &mut (), |c| -> Result<(), ()> {
+            checkpoints.push(*c);
+            Ok(())
+        });
+        // assert
+        assert_eq!(Ok(NavigationState { depth: 60, horizontal: 15, aim: 10 }), result);
+        assert_eq!(vec![2, 4, 6], checkpoints.iter().map(|c| c.command_index).collect::<Vec<usize>>());
+        assert_eq!(hash_course(&course[..4]), checkpoints[1].prefix_hash);
+        assert_eq!(navigate_from(&course[..4], true, NavigationState::default()), checkpoints[1].state);
+    }
+
+    #[test]
+    fn navigation_run_resumes_to_same_result() {
+        // arrange
+        let course = example_course();
+        let mut first_run = NavigationRun::new(true);
+        for op in course[..3].iter() {
+            first_run.step(op);
+        }
+        let checkpoint = first_run.checkpoint();
+        // act
+        let mut resumed = NavigationRun::resume(&checkpoint, &course, true).unwrap();
+        let result = resumed.run(&course, 0, &mut (), |_| -> Result<(), ()> { Ok(()) });
+        // assert
+        assert_eq!(Ok(NavigationState { depth: 60, horizontal: 15, aim: 10 }), result);
+        assert_eq!(6, resumed.command_index());
+        assert_eq!(hash_course(&course), resumed.checkpoint().prefix_hash);
+    }
+
+    #[test]
+    fn navigation_run_notifies_observer_with_course_indexes() {
+        // arrange
+        let course = example_course();
+        let mut first_run = NavigationRun::new(false);
+        for op in course[..3].iter() {
+            first_run.step(op);
+        }
+        let mut resumed = NavigationRun::resume(&first_run.checkpoint(), &course, false).unwrap();
+        let mut alert = DepthThresholdAlert::new(4);
+        // act
+        let result = resumed.run(&course, 0, &mut alert, |_| -> Result<(), ()> { Ok(()) });
+        // assert
+        assert_eq!(Ok(NavigationState { depth: 10, horizontal: 15, aim: 0 }), result);
+        assert_eq!(alert.alerts, vec![
+            DepthAlert { index: 3, from_depth: 5, to_depth: 2 },
+            DepthAlert { index: 4, from_depth: 2, to_depth: 10 },
+        ]);
+    }
+
+    #[test]
+    fn navigation_run_resume_rejects_different_course() {
+        // arrange
+        let course = example_course();
+        let mut first_run = NavigationRun::new(false);
+        first_run.step(&course[0]);
+        first_run.step(&course[1]);
+        let checkpoint = first_run.checkpoint();
+        let mut edited = course.clone();
+        edited[1] = CourseDirection::Down(6);
+        // act/assert
+        assert!(matches!(NavigationRun::resume(&checkpoint, &edited, false), Err(CheckpointError::PrefixMismatch)));
+        assert!(matches!(NavigationRun::resume(&checkpoint, &course[..1], false), Err(CheckpointError::CourseTooShort(2, 1))));
+        assert!(NavigationRun::resume(&checkpoint, &course, false).is_ok());
+    }
+
+    #[test]
+    fn navigation_run_resume_rejects_different_model() {
+        // arrange
+        let course = example_course();
+        let mut first_run = NavigationRun::new(false);
+        first_run.step(&course[0]);
+        let checkpoint = first_run.checkpoint();
+        // act
+        let result = NavigationRun::resume(&checkpoint, &course, true);
+        // assert
+        assert!(matches!(result, Err(CheckpointError::ModelMismatch(false, true))));
+    }
+
+    #[test]
+    fn checkpoint_round_trips_through_file() {
+        // arrange
+        let path = std::env::temp_dir().join("checkpoint_round_trips_through_file.checkpoint");
+        let path = path.to_str().unwrap();
+        let checkpoint = Checkpoint {
+            state: NavigationState { depth: -4, horizontal: 17, aim: 3 },
+            command_index: 12,
+            with_aim: true,
+            prefix_hash: 0xdeadbeef01
+        };
+        // act
+        save_checkpoint(path, &checkpoint).unwrap();
+        let result = load_checkpoint(path).unwrap();
+        // assert
+        assert_eq!(checkpoint, result);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn parse_checkpoint_reports_missing_and_bad_fields() {
+        assert!(matches!(
+            parse_checkpoint(&["depth 1".to_string()]),
+            Err(CheckpointError::MissingField("horizontal"))
+        ));
+        assert!(matches!(
+            parse_checkpoint(&["depth deep".to_string()]),
+            Err(CheckpointError::UnparseableValue(0, v)) if v == "deep"
+        ));
+        assert!(matches!(
+            parse_checkpoint(&["speed 1".to_string()]),
+            Err(CheckpointError::InvalidField(0, f)) if f == "speed"
+        ));
+    }
+}
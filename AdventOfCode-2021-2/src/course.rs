@@ -1,10 +1,24 @@
-#[derive(Debug, PartialEq)]
+use std::fmt;
+use std::io::{self, Write};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum CourseDirection {
     Forward(i32),
     Up(i32),
     Down(i32)
 }
 
+// Writes the direction in the same form parse_course reads, so courses can be saved and reloaded
+impl fmt::Display for CourseDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CourseDirection::Forward(n) => write!(f, "forward {}", n),
+            CourseDirection::Up(n) => write!(f, "up {}", n),
+            CourseDirection::Down(n) => write!(f, "down {}", n)
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum ParseCourseError {
     // When the line has the wrong number of tokens. First is line number, second is the full string
@@ -15,15 +29,43 @@ pub enum ParseCourseError {
     UnparseableNumber(usize, String)
 }
 
-pub fn parse_course(input: &Vec<String>) -> Result<Vec<CourseDirection>, ParseCourseError> {
+impl fmt::Display for ParseCourseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseCourseError::UnexpectedNumberOfTokens(line_num, line) => write!(f, "Line {}: expected a direction and a number, got \"{}\"", line_num + 1, line),
+            ParseCourseError::InvalidCourseString(line_num, name) => write!(f, "Line {}: unknown direction \"{}\"", line_num + 1, name),
+            ParseCourseError::UnparseableNumber(line_num, number) => write!(f, "Line {}: could not parse number \"{}\"", line_num + 1, number)
+        }
+    }
+}
+
+pub fn parse_course(input: &[String]) -> Result<Vec<CourseDirection>, ParseCourseError> {
     input
         .iter()
         .enumerate()
-        .map(|(line_num, line_str)| parse_course_string(line_num, &line_str))
+        .map(|(line_num, line_str)| parse_course_string(line_num, line_str))
         .collect()
 }
 
-fn parse_course_string(line_num: usize, input: &String) -> Result<CourseDirection, ParseCourseError> {
+// Writes a course one direction per line, in the form parse_course reads
+pub fn write_course<W: Write>(writer: &mut W, course: &[CourseDirection]) -> io::Result<()> {
+    for direction in course.iter() {
+        writeln!(writer, "{}", direction)?;
+    }
+    writer.flush()
+}
+
+// The direction that exactly undoes this one under either navigation model. None for Forward(i32::MIN), as moving
+// back by i32::MAX + 1 can't be written as a single command.
+pub fn invert_direction(direction: &CourseDirection) -> Option<CourseDirection> {
+    match direction {
+        CourseDirection::Forward(n) => n.checked_neg().map(CourseDirection::Forward),
+        CourseDirection::Up(n) => Some(CourseDirection::Down(*n)),
+        CourseDirection::Down(n) => Some(CourseDirection::Up(*n))
+    }
+}
+
+pub fn parse_course_string(line_num: usize, input: &str) -> Result<CourseDirection, ParseCourseError> {
     let split_string: Vec<&str> = input.split_whitespace().collect();
     if split_string.len() != 2 {
         Err(ParseCourseError::UnexpectedNumberOfTokens(line_num, input.to_string()))
@@ -44,6 +86,8 @@ mod tests {
     use super::ParseCourseError;
     use super::parse_course_string;
     use super::parse_course;
+    use super::invert_direction;
+    use super::write_course;
 
     #[test]
     fn parse_course_parses_list() {
@@ -165,4 +209,39 @@ mod tests {
             Ok(_) => panic!()
         }
     }
+
+    #[test]
+    fn course_direction_display_round_trips_through_parse() {
+        // arrange
+        let courses = vec![CourseDirection::Forward(5), CourseDirection::Up(-2), CourseDirection::Down(7)];
+        // act
+        let lines: Vec<String> = courses.iter().map(|c| c.to_string()).collect();
+        let result = parse_course(&lines).unwrap();
+        // assert
+        assert_eq!(lines, vec!["forward 5".to_string(), "up -2".to_string(), "down 7".to_string()]);
+        assert_eq!(courses, result);
+    }
+
+    #[test]
+    fn write_course_writes_lines_parse_course_reads() {
+        // arrange
+        let courses = vec![CourseDirection::Down(3), CourseDirection::Forward(12)];
+        let mut output = Vec::new();
+        // act
+        write_course(&mut output, &courses).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        let lines: Vec<String> = text.lines().map(|l| l.to_string()).collect();
+        // assert
+        assert_eq!("down 3\nforward 12\n", text);
+        assert_eq!(courses, parse_course(&lines).unwrap());
+    }
+
+    #[test]
+    fn invert_direction_swaps_and_negates() {
+        assert_eq!(Some(CourseDirection::Forward(-4)), invert_direction(&CourseDirection::Forward(4)));
+        assert_eq!(Some(CourseDirection::Down(3)), invert_direction(&CourseDirection::Up(3)));
+        assert_eq!(Some(CourseDirection::Up(3)), invert_direction(&CourseDirection::Down(3)));
+        assert_eq!(None, invert_direction(&CourseDirection::Forward(i32::MIN)));
+        assert_eq!(Some(CourseDirection::Down(i32::MIN)), invert_direction(&CourseDirection::Up(i32::MIN)));
+    }
 }
\ No newline at end of file
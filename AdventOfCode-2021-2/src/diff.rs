@@ -0,0 +1,264 @@
+use crate::course::CourseDirection;
+use crate::navigation::{navigate, SubCoordinates};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DiffOp {
+    // In both courses
+    Keep(CourseDirection),
+    // Only in the new course
+    Insert(CourseDirection),
+    // Only in the old course
+    Delete(CourseDirection)
+}
+
+impl fmt::Display for DiffOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffOp::Keep(direction) => write!(f, "  {}", direction),
+            DiffOp::Insert(direction) => write!(f, "+ {}", direction),
+            DiffOp::Delete(direction) => write!(f, "- {}", direction)
+        }
+    }
+}
+
+// Where one navigation model takes each course
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ModelDifference {
+    pub with_aim: bool,
+    pub old: SubCoordinates,
+    pub new: SubCoordinates
+}
+
+impl ModelDifference {
+    pub fn depth_change(&self) -> i32 {
+        self.new.depth() - self.old.depth()
+    }
+
+    pub fn horizontal_change(&self) -> i32 {
+        self.new.horizontal() - self.old.horizontal()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CourseDiff {
+    pub ops: Vec<DiffOp>,
+    // One entry for each navigation model, simple first
+    pub models: Vec<ModelDifference>
+}
+
+// A region that both sides of a merge changed in different ways. Start is its position in the base course.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MergeConflict {
+    pub base_start: usize,
+    pub base: Vec<CourseDirection>,
+    pub ours: Vec<CourseDirection>,
+    pub theirs: Vec<CourseDirection>
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |course: &[CourseDirection]| course.iter().map(|c| c.to_string()).collect::<Vec<String>>().join(", ");
+        write!(
+            f,
+            "Conflict at base command {}: base [{}], ours [{}], theirs [{}]",
+            self.base_start + 1, join(&self.base), join(&self.ours), join(&self.theirs)
+        )
+    }
+}
+
+pub fn diff_courses(old: &[CourseDirection], new: &[CourseDirection]) -> CourseDiff {
+    let matches = longest_common_subsequence(old, new);
+    let mut ops = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    for (old_index, new_index) in matches.into_iter().chain([(old.len(), new.len())]) {
+        ops.extend(old[i..old_index].iter().map(|&d| DiffOp::Delete(d)));
+        ops.extend(new[j..new_index].iter().map(|&d| DiffOp::Insert(d)));
+        if old_index < old.len() {
+            ops.push(DiffOp::Keep(old[old_index]));
+        }
+        i = old_index + 1;
+        j = new_index + 1;
+    }
+
+    let models = [false, true]
+        .iter()
+        .map(|&with_aim| ModelDifference { with_aim, old: navigate(old, with_aim), new: navigate(new, with_aim) })
+        .collect();
+
+    CourseDiff { ops, models }
+}
+
+// Merges two independent edits of a base course. Regions changed by only one side take that side's change, and
+// regions both sides changed identically are taken once. Where both sides changed a region differently the merge
+// fails, reporting every such region.
+pub fn merge_courses(base: &[CourseDirection], ours: &[CourseDirection], theirs: &[CourseDirection]) -> Result<Vec<CourseDirection>, Vec<MergeConflict>> {
+    let ours_match = match_base(base, ours);
+    let theirs_match = match_base(base, theirs);
+    let mut merged = Vec::with_capacity(base.len());
+    let mut conflicts = Vec::new();
+    let (mut i, mut j, mut k) = (0, 0, 0);
+
+    loop {
+        // Copy across commands that neither side touched
+        while i < base.len() && ours_match[i] == Some(j) && theirs_match[i] == Some(k) {
+            merged.push(base[i]);
+            i += 1;
+            j += 1;
+            k += 1;
+        }
+        if i == base.len() && j == ours.len() && k == theirs.len() {
+            break;
+        }
+
+        // The changed region runs up to the next command both sides kept
+        let (next_i, next_j, next_k) = (i..base.len())
+            .find_map(|x| Some((x, ours_match[x]?, theirs_match[x]?)))
+            .unwrap_or((base.len(), ours.len(), theirs.len()));
+        let (base_chunk, ours_chunk, theirs_chunk) = (&base[i..next_i], &ours[j..next_j], &theirs[k..next_k]);
+
+        if ours_chunk == base_chunk {
+            merged.extend_from_slice(theirs_chunk);
+        } else if theirs_chunk == base_chunk || ours_chunk == theirs_chunk {
+            merged.extend_from_slice(ours_chunk);
+        } else {
+            conflicts.push(MergeConflict {
+                base_start: i,
+                base: base_chunk.to_vec(),
+                ours: ours_chunk.to_vec(),
+                theirs: theirs_chunk.to_vec()
+            });
+        }
+        (i, j, k) = (next_i, next_j, next_k);
+    }
+
+    if conflicts.is_empty() {
+        Ok(merged)
+    } else {
+        Err(conflicts)
+    }
+}
+
+// For each base command, where it appears in the edited course, if it was kept
+fn match_base(base: &[CourseDirection], edited: &[CourseDirection]) -> Vec<Option<usize>> {
+    let mut matched = vec![None; base.len()];
+    for (base_index, edited_index) in longest_common_subsequence(base, edited) {
+        matched[base_index] = Some(edited_index);
+    }
+    matched
+}
+
+// Index pairs of one longest common subsequence, in increasing order
+fn longest_common_subsequence(a: &[CourseDirection], b: &[CourseDirection]) -> Vec<(usize, usize)> {
+    // lengths[i][j] is the LCS length of a[i..] and b[j..]
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::with_capacity(lengths[0][0]);
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::course::CourseDirection::{self, Down, Forward, Up};
+    use crate::navigation::SubCoordinates;
+    use super::diff_courses;
+    use super::merge_courses;
+    use super::DiffOp;
+    use super::MergeConflict;
+
+    fn base_course() -> Vec<CourseDirection> {
+        vec![Forward(5), Down(5), Forward(8), Up(3), Down(8), Forward(2)]
+    }
+
+    #[test]
+    fn diff_courses_lists_kept_inserted_and_deleted() {
+        // arrange
+        let new = vec![Forward(5), Forward(8), Up(3), Down(1), Down(8), Forward(2)];
+        // act
+        let result = diff_courses(&base_course(), &new);
+        // assert
+        assert_eq!(result.ops, vec![
+            DiffOp::Keep(Forward(5)),
+            DiffOp::Delete(Down(5)),
+            DiffOp::Keep(Forward(8)),
+            DiffOp::Keep(Up(3)),
+            DiffOp::Insert(Down(1)),
+            DiffOp::Keep(Down(8)),
+            DiffOp::Keep(Forward(2)),
+        ]);
+    }
+
+    #[test]
+    fn diff_courses_reports_final_coordinates_per_model() {
+        // arrange
+        let new = vec![Forward(5), Down(5), Forward(10)];
+        // act
+        let result = diff_courses(&base_course(), &new);
+        // assert
+        assert_eq!(2, result.models.len());
+        assert!(!result.models[0].with_aim);
+        assert_eq!(SubCoordinates::new(5, 15), result.models[0].new);
+        assert_eq!(-5, result.models[0].depth_change());
+        assert_eq!(0, result.models[0].horizontal_change());
+        assert_eq!(SubCoordinates::new(60, 15), result.models[1].old);
+        assert_eq!(-10, result.models[1].depth_change());
+    }
+
+    #[test]
+    fn merge_courses_combines_separate_edits() {
+        // arrange
+        let ours = vec![Forward(5), Down(6), Forward(8), Up(3), Down(8), Forward(2)];
+        let theirs = vec![Forward(5), Down(5), Forward(8), Up(3), Down(8), Forward(2), Forward(9)];
+        // act
+        let result = merge_courses(&base_course(), &ours, &theirs);
+        // assert
+        assert_eq!(result, Ok(vec![Forward(5), Down(6), Forward(8), Up(3), Down(8), Forward(2), Forward(9)]));
+    }
+
+    #[test]
+    fn merge_courses_takes_identical_edits_once() {
+        // arrange
+        let edited = vec![Forward(5), Forward(8), Up(3), Down(8), Forward(2)];
+        // act
+        let result = merge_courses(&base_course(), &edited, &edited);
+        // assert
+        assert_eq!(result, Ok(edited));
+    }
+
+    #[test]
+    fn merge_courses_flags_conflicting_edits_to_same_region() {
+        // arrange
+        let ours = vec![Forward(5), Down(6), Forward(8), Up(3), Down(8), Forward(2)];
+        let theirs = vec![Forward(5), Down(7), Forward(8), Up(3), Down(8), Forward(2)];
+        // act
+        let result = merge_courses(&base_course(), &ours, &theirs);
+        // assert
+        assert_eq!(result, Err(vec![MergeConflict {
+            base_start: 1,
+            base: vec![Down(5)],
+            ours: vec![Down(6)],
+            theirs: vec![Down(7)]
+        }]));
+    }
+}
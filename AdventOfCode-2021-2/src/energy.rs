@@ -0,0 +1,230 @@
+use crate::course::CourseDirection;
+use crate::file::is_blank_or_comment;
+use crate::navigation::{trajectory, NavigationState};
+use std::fmt;
+
+// Costs charged for each unit of movement. The same model works with or without aim, as it is
+// charged on the change in state each command makes rather than on the command itself.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct EnergyModel {
+    // Per unit moved forward
+    pub forward_cost: f64,
+    // Extra per unit of depth gained or lost
+    pub depth_change_cost: f64,
+    // Per unit travelled (forward or vertically), for each unit of average depth along the way
+    pub pressure_cost: f64,
+    // Per unit the aim changes by
+    pub aim_change_cost: f64
+}
+
+impl Default for EnergyModel {
+    fn default() -> Self {
+        Self {
+            forward_cost: 1.0,
+            depth_change_cost: 1.0,
+            pressure_cost: 0.0,
+            aim_change_cost: 0.0
+        }
+    }
+}
+
+impl EnergyModel {
+    pub fn segment_cost(&self, from: &NavigationState, to: &NavigationState) -> f64 {
+        let forward = (to.horizontal - from.horizontal).abs() as f64;
+        let depth_change = (to.depth - from.depth).abs() as f64;
+        let aim_change = (to.aim - from.aim).abs() as f64;
+        // Depth varies linearly along a segment, and there is no extra pressure above the surface
+        let average_depth = ((from.depth + to.depth) as f64 / 2.0).max(0.0);
+
+        forward * self.forward_cost
+            + depth_change * self.depth_change_cost
+            + (forward + depth_change) * average_depth * self.pressure_cost
+            + aim_change * self.aim_change_cost
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct EnergyReport {
+    // The energy used by each command, in course order
+    pub segments: Vec<f64>,
+    pub total: f64
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FuelExhausted {
+    // Index of the command that can't be completed with the fuel left
+    pub command_index: usize,
+    // Fuel used by the commands before it
+    pub used: f64,
+    pub budget: f64
+}
+
+impl fmt::Display for FuelExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fuel runs out on command {}, having used {} of a budget of {}", self.command_index + 1, self.used, self.budget)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseEnergyModelError {
+    // When the line has the wrong number of tokens. First is line number, second is the full string
+    UnexpectedNumberOfTokens(usize, String),
+    // When the line doesn't name a known cost (forward, depth, pressure, aim). Second value is the invalid name.
+    InvalidCostName(usize, String),
+    // When the cost can't be parsed. Second value is the unparseable number.
+    UnparseableNumber(usize, String)
+}
+
+impl fmt::Display for ParseEnergyModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseEnergyModelError::UnexpectedNumberOfTokens(line_num, line) => write!(f, "Line {}: expected a cost name and a number, got \"{}\"", line_num + 1, line),
+            ParseEnergyModelError::InvalidCostName(line_num, name) => write!(f, "Line {}: unknown cost \"{}\"", line_num + 1, name),
+            ParseEnergyModelError::UnparseableNumber(line_num, number) => write!(f, "Line {}: could not parse number \"{}\"", line_num + 1, number)
+        }
+    }
+}
+
+// Energy model files hold one "<cost> <amount>" per line, where the cost is forward, depth, pressure or aim.
+// Costs that aren't listed keep their default. Blank lines and lines starting with # are ignored.
+pub fn parse_energy_model(input: &[String]) -> Result<EnergyModel, ParseEnergyModelError> {
+    let mut model = EnergyModel::default();
+    for (line_num, line_str) in input.iter().enumerate().filter(|(_, line_str)| !is_blank_or_comment(line_str)) {
+        let split_string: Vec<&str> = line_str.split_whitespace().collect();
+        if split_string.len() != 2 {
+            return Err(ParseEnergyModelError::UnexpectedNumberOfTokens(line_num, line_str.to_string()));
+        }
+
+        let cost = split_string[1].parse::<f64>().map_err(|_| ParseEnergyModelError::UnparseableNumber(line_num, split_string[1].to_string()))?;
+        match split_string[0] {
+            "forward" => model.forward_cost = cost,
+            "depth" => model.depth_change_cost = cost,
+            "pressure" => model.pressure_cost = cost,
+            "aim" => model.aim_change_cost = cost,
+            _ => return Err(ParseEnergyModelError::InvalidCostName(line_num, split_string[0].to_string()))
+        }
+    }
+
+    Ok(model)
+}
+
+pub fn energy_consumption(operations: &[CourseDirection], with_aim: bool, model: &EnergyModel) -> EnergyReport {
+    let segments: Vec<f64> = trajectory(operations, with_aim, NavigationState::default())
+        .windows(2)
+        .map(|segment| model.segment_cost(&segment[0], &segment[1]))
+        .collect();
+    let total = segments.iter().sum();
+
+    EnergyReport { segments, total }
+}
+
+pub fn check_fuel_budget(operations: &[CourseDirection], with_aim: bool, model: &EnergyModel, budget: f64) -> Result<EnergyReport, FuelExhausted> {
+    let report = energy_consumption(operations, with_aim, model);
+    let mut used = 0.0;
+    for (command_index, cost) in report.segments.iter().enumerate() {
+        if used + cost > budget {
+            return Err(FuelExhausted { command_index, used, budget });
+        }
+        used += cost;
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::course::CourseDirection;
+    use crate::navigation::NavigationState;
+    use super::check_fuel_budget;
+    use super::energy_consumption;
+    use super::parse_energy_model;
+    use super::EnergyModel;
+    use super::FuelExhausted;
+    use super::ParseEnergyModelError;
+
+    fn example_course() -> Vec<CourseDirection> {
+        vec![
+            CourseDirection::Forward(5),
+            CourseDirection::Down(5),
+            CourseDirection::Forward(8),
+            CourseDirection::Up(3),
+            CourseDirection::Down(8),
+            CourseDirection::Forward(2),
+        ]
+    }
+
+    #[test]
+    fn energy_consumption_default_model_counts_distance() {
+        // arrange
+        let model = EnergyModel::default();
+        // act
+        let result = energy_consumption(&example_course(), false, &model);
+        // assert
+        assert_eq!(result.segments, vec![5.0, 5.0, 8.0, 3.0, 8.0, 2.0]);
+        assert_eq!(result.total, 31.0);
+    }
+
+    #[test]
+    fn energy_consumption_with_aim_charges_aim_changes() {
+        // arrange
+        let model = EnergyModel { forward_cost: 1.0, depth_change_cost: 0.5, pressure_cost: 0.0, aim_change_cost: 2.0 };
+        let course = vec![CourseDirection::Down(2), CourseDirection::Forward(3)];
+        // act
+        let result = energy_consumption(&course, true, &model);
+        // assert
+        // The forward move descends 6 units as well as moving 3 along
+        assert_eq!(result.segments, vec![4.0, 6.0]);
+    }
+
+    #[test]
+    fn segment_cost_includes_pressure_at_average_depth() {
+        // arrange
+        let model = EnergyModel { forward_cost: 0.0, depth_change_cost: 0.0, pressure_cost: 0.1, aim_change_cost: 0.0 };
+        let from = NavigationState { depth: 10, horizontal: 0, aim: 0 };
+        let to = NavigationState { depth: 30, horizontal: 0, aim: 0 };
+        // act
+        let result = model.segment_cost(&from, &to);
+        // assert
+        assert!((result - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn check_fuel_budget_reports_command_where_fuel_runs_out() {
+        // arrange
+        let model = EnergyModel::default();
+        // act
+        let within = check_fuel_budget(&example_course(), false, &model, 31.0);
+        let exceeded = check_fuel_budget(&example_course(), false, &model, 20.0);
+        // assert
+        assert!(within.is_ok());
+        assert_eq!(exceeded, Err(FuelExhausted { command_index: 3, used: 18.0, budget: 20.0 }));
+    }
+
+    #[test]
+    fn parse_energy_model_overrides_listed_costs() {
+        // arrange
+        let lines = vec!["pressure 0.25".to_string(), "aim 3".to_string()];
+        // act
+        let result = parse_energy_model(&lines).unwrap();
+        // assert
+        assert_eq!(result, EnergyModel { forward_cost: 1.0, depth_change_cost: 1.0, pressure_cost: 0.25, aim_change_cost: 3.0 });
+        assert_eq!(
+            parse_energy_model(&["thrust 1".to_string()]),
+            Err(ParseEnergyModelError::InvalidCostName(0, "thrust".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_energy_model_skips_blank_lines_and_comments() {
+        // arrange
+        let lines: Vec<String> = ["# Costs for the survey", "", "forward 2", "   ", "  # deeper is dearer", "pressure 0.5", "thrust 1"]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        // act
+        let result = parse_energy_model(&lines[..6]).unwrap();
+        // assert
+        assert_eq!(result, EnergyModel { forward_cost: 2.0, depth_change_cost: 1.0, pressure_cost: 0.5, aim_change_cost: 0.0 });
+        assert_eq!(parse_energy_model(&lines), Err(ParseEnergyModelError::InvalidCostName(6, "thrust".to_string())));
+    }
+}
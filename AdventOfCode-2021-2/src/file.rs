@@ -12,9 +12,33 @@ pub fn load_string_txt_to_vector(path: &String) -> Result<Vec<String>, std::io::
     strings
 }
 
+// Config files such as obstacle maps and energy models may contain blank lines and lines starting with #, which
+// are skipped
+pub fn is_blank_or_comment(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+pub fn load_number_txt_to_vector(path: &String) -> Result<Vec<i32>, std::io::Error> {
+    let mut numbers = Vec::<i32>::new();
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+
+    for line_result in reader.lines() {
+        let line = line_result?;
+        let number: i32 = line.trim().parse().map_err(|e| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Failed to parse line: {:?}", e))
+        })?;
+        numbers.push(number);
+    }
+
+    Ok(numbers)
+}
+
 #[cfg(test)]
 mod tests {
     use super::load_string_txt_to_vector;
+    use super::load_number_txt_to_vector;
 
     #[test]
     fn test_load_string_txt_to_vector_can_load_data_file() {
@@ -48,4 +72,32 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_load_number_txt_to_vector_can_load_data_file() {
+        // arrange
+        let file_path = String::from("./test_data/test_depths.txt");
+        // act
+        let result = load_number_txt_to_vector(&file_path);
+        // assert
+        match result {
+            Err(_) => panic!(),
+            Ok(v) => assert_eq!(vec![1, 2, 3, 4, 5], v)
+        }
+    }
+
+    #[test]
+    fn test_load_number_text_to_vector_gracefully_handles_non_number() {
+        // arrange
+        let file_path = String::from("./test_data/test_file.txt");
+        // act
+        let result = load_number_txt_to_vector(&file_path);
+        // assert
+        match result {
+            Ok(_) => panic!(),
+            Err(e) => {
+                assert_eq!(std::io::ErrorKind::InvalidData, e.kind());
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,218 @@
+use crate::course::CourseDirection;
+use crate::navigation::{parse_navigation_model, NavigationState, SubCoordinates};
+use std::fmt;
+
+pub struct FleetMember {
+    pub name: String,
+    pub course: Vec<CourseDirection>,
+    // Where this submarine starts, offset from the shared origin
+    pub start: NavigationState,
+    pub with_aim: bool
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ProximityEvent {
+    // Tick 0 is the starting positions, tick n is while every submarine runs its nth command
+    pub tick: usize,
+    // Indexes of the two submarines in the fleet, first always lower than second
+    pub first: usize,
+    pub second: usize,
+    // The closest the two came during the tick
+    pub distance: f64
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FleetReport {
+    pub final_coordinates: Vec<SubCoordinates>,
+    pub events: Vec<ProximityEvent>,
+    pub ticks: usize
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FleetSpecError {
+    // When the start part of a spec isn't "<depth>,<horizontal>". Value is the invalid start.
+    InvalidStart(String)
+}
+
+impl fmt::Display for FleetSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FleetSpecError::InvalidStart(start) => write!(f, "Invalid start \"{}\", expected <depth>,<horizontal>", start)
+        }
+    }
+}
+
+// Splits a fleet member spec of the form "<file>[:<model>[:<depth>,<horizontal>]]" into its course file, whether
+// it navigates with aim, and its start. Members default to the simple model starting at the origin. The spec is
+// read from the right, and anything that isn't a model or a start is left in the file, so paths can hold colons.
+pub fn parse_fleet_member_spec(spec: &str) -> Result<(String, bool, NavigationState), FleetSpecError> {
+    if let Some((file, name)) = spec.rsplit_once(':') {
+        if let Some(with_aim) = parse_navigation_model(name) {
+            return Ok((file.to_string(), with_aim, NavigationState::default()));
+        }
+    }
+    let parts: Vec<&str> = spec.rsplitn(3, ':').collect();
+    if let [start, name, file] = parts[..] {
+        if let Some(with_aim) = parse_navigation_model(name) {
+            let start = parse_start(start).ok_or(FleetSpecError::InvalidStart(start.to_string()))?;
+            return Ok((file.to_string(), with_aim, start));
+        }
+    }
+
+    Ok((spec.to_string(), false, NavigationState::default()))
+}
+
+fn parse_start(start: &str) -> Option<NavigationState> {
+    let (depth, horizontal) = start.split_once(',')?;
+    Some(NavigationState {
+        depth: depth.trim().parse().ok()?,
+        horizontal: horizontal.trim().parse().ok()?,
+        aim: 0
+    })
+}
+
+// Runs every member's course in lockstep, one command each per tick, recording every tick at which
+// a pair of submarines is closer than the minimum separation. Submarines that finish early hold position.
+pub fn simulate_fleet(members: &[FleetMember], min_separation: f64) -> FleetReport {
+    let mut states: Vec<NavigationState> = members.iter().map(|m| m.start).collect();
+    let ticks = members.iter().map(|m| m.course.len()).max().unwrap_or(0);
+    let mut events = Vec::new();
+
+    record_proximity(0, &states, &states, min_separation, &mut events);
+    for tick in 1..=ticks {
+        let previous_states = states.clone();
+        for (member, state) in members.iter().zip(states.iter_mut()) {
+            if let Some(direction) = member.course.get(tick - 1) {
+                state.apply(direction, member.with_aim);
+            }
+        }
+        record_proximity(tick, &previous_states, &states, min_separation, &mut events);
+    }
+
+    FleetReport {
+        final_coordinates: states.iter().map(|s| s.coordinates()).collect(),
+        events,
+        ticks
+    }
+}
+
+// Each submarine moves in a straight line at a steady speed from its state before the tick to its state after, so
+// two that pass each other part way through a tick are still caught
+fn record_proximity(tick: usize, from: &[NavigationState], to: &[NavigationState], min_separation: f64, events: &mut Vec<ProximityEvent>) {
+    for first in 0..to.len() {
+        for second in first + 1..to.len() {
+            let distance = closest_approach((&from[first], &to[first]), (&from[second], &to[second]));
+            if distance < min_separation {
+                events.push(ProximityEvent { tick, first, second, distance });
+            }
+        }
+    }
+}
+
+// The smallest distance between two submarines moving between their from and to states over the same time
+fn closest_approach(a: (&NavigationState, &NavigationState), b: (&NavigationState, &NavigationState)) -> f64 {
+    let offset = |a: &NavigationState, b: &NavigationState| (a.depth as f64 - b.depth as f64, a.horizontal as f64 - b.horizontal as f64);
+    let (start_depth, start_horizontal) = offset(a.0, b.0);
+    let (end_depth, end_horizontal) = offset(a.1, b.1);
+    let (depth_change, horizontal_change) = (end_depth - start_depth, end_horizontal - start_horizontal);
+
+    // The fraction of the way through the move at which the offset is shortest
+    let change_squared = depth_change * depth_change + horizontal_change * horizontal_change;
+    let closest = if change_squared == 0.0 {
+        0.0
+    } else {
+        (-(start_depth * depth_change + start_horizontal * horizontal_change) / change_squared).clamp(0.0, 1.0)
+    };
+    (start_depth + closest * depth_change).hypot(start_horizontal + closest * horizontal_change)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::course::CourseDirection;
+    use crate::navigation::{NavigationState, SubCoordinates};
+    use super::parse_fleet_member_spec;
+    use super::simulate_fleet;
+    use super::FleetMember;
+    use super::FleetSpecError;
+    use super::ProximityEvent;
+
+    fn member(course: Vec<CourseDirection>, start: NavigationState, with_aim: bool) -> FleetMember {
+        FleetMember { name: "sub".to_string(), course, start, with_aim }
+    }
+
+    #[test]
+    fn simulate_fleet_reports_final_coordinates_per_sub() {
+        // arrange
+        let course = vec![CourseDirection::Down(2), CourseDirection::Forward(3)];
+        let fleet = vec![
+            member(course.clone(), NavigationState::default(), false),
+            member(course, NavigationState { depth: 10, horizontal: 100, aim: 0 }, true),
+        ];
+        // act
+        let result = simulate_fleet(&fleet, 1.0);
+        // assert
+        assert_eq!(result.final_coordinates, vec![SubCoordinates::new(2, 3), SubCoordinates::new(16, 103)]);
+        assert_eq!(result.ticks, 2);
+        assert!(result.events.is_empty());
+    }
+
+    #[test]
+    fn simulate_fleet_records_each_tick_within_separation() {
+        // arrange
+        let fleet = vec![
+            member(vec![CourseDirection::Forward(4), CourseDirection::Forward(4)], NavigationState::default(), false),
+            member(vec![CourseDirection::Down(3)], NavigationState { depth: 0, horizontal: 4, aim: 0 }, false),
+        ];
+        // act
+        let result = simulate_fleet(&fleet, 4.0);
+        // assert
+        // The pair are closest part way through the first tick, and still in range as the second begins
+        assert_eq!(result.events, vec![
+            ProximityEvent { tick: 1, first: 0, second: 1, distance: 2.4 },
+            ProximityEvent { tick: 2, first: 0, second: 1, distance: 3.0 },
+        ]);
+    }
+
+    #[test]
+    fn simulate_fleet_records_submarines_crossing_within_a_tick() {
+        // arrange
+        let fleet = vec![
+            member(vec![CourseDirection::Forward(10)], NavigationState::default(), false),
+            member(vec![CourseDirection::Forward(-10)], NavigationState { depth: 0, horizontal: 10, aim: 0 }, false),
+        ];
+        // act
+        let result = simulate_fleet(&fleet, 1.0);
+        // assert
+        // Both ends of the tick are 10 apart, but they pass through each other half way
+        assert_eq!(result.events, vec![ProximityEvent { tick: 1, first: 0, second: 1, distance: 0.0 }]);
+    }
+
+    #[test]
+    fn parse_fleet_member_spec_reads_model_and_start() {
+        assert_eq!(
+            parse_fleet_member_spec("course.txt"),
+            Ok(("course.txt".to_string(), false, NavigationState::default()))
+        );
+        assert_eq!(
+            parse_fleet_member_spec("course.txt:aim:5,-2"),
+            Ok(("course.txt".to_string(), true, NavigationState { depth: 5, horizontal: -2, aim: 0 }))
+        );
+        assert_eq!(parse_fleet_member_spec("c.txt:aim:5"), Err(FleetSpecError::InvalidStart("5".to_string())));
+    }
+
+    #[test]
+    fn parse_fleet_member_spec_keeps_colons_in_the_path() {
+        assert_eq!(
+            parse_fleet_member_spec("C:/data/course.txt"),
+            Ok(("C:/data/course.txt".to_string(), false, NavigationState::default()))
+        );
+        assert_eq!(
+            parse_fleet_member_spec("C:/data/course.txt:aim"),
+            Ok(("C:/data/course.txt".to_string(), true, NavigationState::default()))
+        );
+        assert_eq!(
+            parse_fleet_member_spec("runs:1/course.txt:simple:3,4"),
+            Ok(("runs:1/course.txt".to_string(), false, NavigationState { depth: 3, horizontal: 4, aim: 0 }))
+        );
+    }
+}
@@ -0,0 +1,536 @@
+use crate::course::CourseDirection;
+use crate::navigation::{NavigationState, SubCoordinates};
+use crate::planner::{plan_course, PlanError, PlanOptions};
+use std::fmt;
+
+// SplitMix64, a small generator whose output depends only on its seed, so a seed is all that's needed to
+// reproduce a course on any machine or build
+pub struct SeededRng(u64);
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Uniform in [0, n), for n greater than 0
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+}
+
+// How the amount of each generated command is chosen
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Magnitude {
+    // Always this amount
+    Fixed(i32),
+    // Any amount from the first to the second inclusive, equally likely
+    Uniform(i32, i32),
+    // Mostly small amounts with an occasional long one, averaging this amount. Never less than 1.
+    Geometric(f64)
+}
+
+impl Magnitude {
+    pub fn sample(&self, rng: &mut SeededRng) -> i32 {
+        match *self {
+            Magnitude::Fixed(n) => n,
+            Magnitude::Uniform(min, max) => min + rng.below((max - min) as u64 + 1) as i32,
+            Magnitude::Geometric(mean) => {
+                if mean <= 1.0 {
+                    return 1;
+                }
+                // 1 - next_f64 is in (0, 1], so the logarithm is finite
+                let trials = ((1.0 - rng.next_f64()).ln() / (1.0 - 1.0 / mean).ln()).floor();
+                (1.0 + trials).min(i32::MAX as f64) as i32
+            }
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match *self {
+            Magnitude::Fixed(n) => n >= 0,
+            Magnitude::Uniform(min, max) => 0 <= min && min <= max,
+            Magnitude::Geometric(mean) => mean.is_finite() && mean >= 1.0
+        }
+    }
+}
+
+// Reads "<n>" as a fixed amount, "<min>-<max>" as a uniform range and "~<mean>" as a geometric distribution
+pub fn parse_magnitude(spec: &str) -> Option<Magnitude> {
+    let magnitude = if let Some(mean) = spec.strip_prefix('~') {
+        Magnitude::Geometric(mean.trim().parse().ok()?)
+    } else if let Some((min, max)) = spec.split_once('-') {
+        Magnitude::Uniform(min.trim().parse().ok()?, max.trim().parse().ok()?)
+    } else {
+        Magnitude::Fixed(spec.trim().parse().ok()?)
+    };
+    Some(magnitude).filter(Magnitude::is_valid)
+}
+
+// Relative weights of each command type
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CommandMix {
+    pub forward: u32,
+    pub up: u32,
+    pub down: u32
+}
+
+// Roughly the mix of the puzzle inputs
+impl Default for CommandMix {
+    fn default() -> Self {
+        Self { forward: 5, up: 2, down: 3 }
+    }
+}
+
+enum Command {
+    Forward,
+    Up,
+    Down
+}
+
+impl CommandMix {
+    fn total(&self) -> u64 {
+        self.forward as u64 + self.up as u64 + self.down as u64
+    }
+
+    fn pick(&self, rng: &mut SeededRng) -> Command {
+        let roll = rng.below(self.total());
+        if roll < self.forward as u64 {
+            Command::Forward
+        } else if roll < self.forward as u64 + self.up as u64 {
+            Command::Up
+        } else {
+            Command::Down
+        }
+    }
+}
+
+// Reads "<forward>,<up>,<down>" weights
+pub fn parse_command_mix(spec: &str) -> Option<CommandMix> {
+    let weights: Vec<u32> = spec.split(',').map(|w| w.trim().parse().ok()).collect::<Option<_>>()?;
+    match weights[..] {
+        [forward, up, down] => Some(CommandMix { forward, up, down }),
+        _ => None
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct GeneratorOptions {
+    pub seed: u64,
+    // How many random commands to generate, before any commands added to reach the target
+    pub commands: usize,
+    pub mix: CommandMix,
+    pub forward_magnitude: Magnitude,
+    // Amounts for up and down commands
+    pub depth_magnitude: Magnitude,
+    // The navigation model that the depth guarantee and target apply under
+    pub with_aim: bool,
+    // Whether to keep the submarine from ever rising above the surface
+    pub non_negative_depth: bool,
+    // The deepest the submarine may go. Under the aim model depths grow quickly, so long courses need a limit.
+    pub max_depth: Option<i32>,
+    // Where the course should finish. The random commands stop short of it, then a planned course closes the gap.
+    pub target: Option<SubCoordinates>
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            commands: 1000,
+            mix: CommandMix::default(),
+            forward_magnitude: Magnitude::Uniform(1, 9),
+            depth_magnitude: Magnitude::Uniform(1, 9),
+            with_aim: false,
+            non_negative_depth: true,
+            max_depth: None,
+            target: None
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GeneratorError {
+    // When every command weight is zero
+    EmptyCommandMix,
+    // When a magnitude could produce negative or no amounts. Value is the offending magnitude.
+    InvalidMagnitude(Magnitude),
+    // When the deepest allowed depth is above the surface. Value is the depth.
+    InvalidMaxDepth(i32),
+    // When the target can't be reached from where the random commands finish
+    TargetUnreachable(PlanError),
+    // When reaching the target would take the depth, horizontal position or aim outside the range of an i32
+    TargetOutOfRange(SubCoordinates)
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeneratorError::EmptyCommandMix => write!(f, "Command mix needs at least one non-zero weight"),
+            GeneratorError::InvalidMagnitude(magnitude) => write!(f, "Invalid magnitude {:?}", magnitude),
+            GeneratorError::InvalidMaxDepth(depth) => write!(f, "Maximum depth {} is above the surface", depth),
+            GeneratorError::TargetUnreachable(e) => write!(f, "Target can't be reached: {}", e),
+            GeneratorError::TargetOutOfRange(target) => write!(f, "Target depth {}, horizontal {} can't be reached without overflowing", target.depth(), target.horizontal())
+        }
+    }
+}
+
+pub fn generate_course(options: &GeneratorOptions) -> Result<Vec<CourseDirection>, GeneratorError> {
+    if options.mix.total() == 0 {
+        return Err(GeneratorError::EmptyCommandMix);
+    }
+    if let Some(magnitude) = [options.forward_magnitude, options.depth_magnitude].into_iter().find(|m| !m.is_valid()) {
+        return Err(GeneratorError::InvalidMagnitude(magnitude));
+    }
+    if let Some(max_depth) = options.max_depth.filter(|&depth| depth < 0) {
+        return Err(GeneratorError::InvalidMaxDepth(max_depth));
+    }
+
+    // With a target, forward moves stop short of its horizontal so the planned tail never has to reverse. Under
+    // the aim model one unit is held back, as changing depth then needs some forward movement.
+    let horizontal_limit = options.target.map(|target| if options.with_aim { target.horizontal() - 1 } else { target.horizontal() });
+
+    let mut rng = SeededRng::new(options.seed);
+    let mut state = NavigationState::default();
+    let mut course = Vec::with_capacity(options.commands);
+    for _ in 0..options.commands {
+        let Some(op) = next_command(&mut rng, options, &state, horizontal_limit) else {
+            break;
+        };
+        let Some((op, next_state)) = keep_in_range(op, &state, options.with_aim) else {
+            break;
+        };
+        state = next_state;
+        course.push(op);
+    }
+
+    if let Some(target) = options.target {
+        course.extend(plan_tail(&target, state, options)?);
+    }
+
+    Ok(course)
+}
+
+// Plans from where the random commands finish to the target. Under the aim model the aim is levelled out first, as
+// the planner's arithmetic scales with the starting aim, which a long random course can leave very large.
+fn plan_tail(target: &SubCoordinates, mut state: NavigationState, options: &GeneratorOptions) -> Result<Vec<CourseDirection>, GeneratorError> {
+    let out_of_range = GeneratorError::TargetOutOfRange(*target);
+    if target.depth().checked_sub(state.depth).is_none() || target.horizontal().checked_sub(state.horizontal).is_none() {
+        return Err(out_of_range);
+    }
+
+    let mut tail = Vec::new();
+    if options.with_aim && state.aim != 0 {
+        let level = if state.aim > 0 { CourseDirection::Up(state.aim) } else { CourseDirection::Down(-state.aim) };
+        state = checked_apply(&state, &level, true).ok_or(GeneratorError::TargetOutOfRange(*target))?;
+        tail.push(level);
+    }
+
+    let plan_options = PlanOptions {
+        start: state,
+        with_aim: options.with_aim,
+        max_depth: options.max_depth,
+        allow_negative_depth: !options.non_negative_depth,
+        ..PlanOptions::default()
+    };
+    let planned = plan_course(target, &plan_options).map_err(GeneratorError::TargetUnreachable)?;
+    planned
+        .iter()
+        .try_fold(state, |state, op| checked_apply(&state, op, options.with_aim))
+        .ok_or(out_of_range)?;
+    tail.extend(planned);
+    Ok(tail)
+}
+
+// Swaps a command that would overflow the depth, horizontal position or aim for one that doesn't: a depth or aim
+// change goes the other way, and a forward move that would overflow the depth levels out instead. None when no
+// command fits, which only happens once the horizontal position itself is at the limit.
+fn keep_in_range(op: CourseDirection, state: &NavigationState, with_aim: bool) -> Option<(CourseDirection, NavigationState)> {
+    if let Some(next_state) = checked_apply(state, &op, with_aim) {
+        return Some((op, next_state));
+    }
+    let alternative = match op {
+        CourseDirection::Up(n) => CourseDirection::Down(n),
+        CourseDirection::Down(n) => CourseDirection::Up(n),
+        CourseDirection::Forward(_) if with_aim && state.aim > 0 => CourseDirection::Up(state.aim),
+        CourseDirection::Forward(_) if with_aim && state.aim < 0 => CourseDirection::Down(-state.aim),
+        CourseDirection::Forward(_) => return None
+    };
+    checked_apply(state, &alternative, with_aim).map(|next_state| (alternative, next_state))
+}
+
+// NavigationState::apply, but None instead of overflowing
+fn checked_apply(state: &NavigationState, op: &CourseDirection, with_aim: bool) -> Option<NavigationState> {
+    let mut next = *state;
+    match (op, with_aim) {
+        (CourseDirection::Up(n), true) => next.aim = state.aim.checked_sub(*n)?,
+        (CourseDirection::Up(n), false) => next.depth = state.depth.checked_sub(*n)?,
+        (CourseDirection::Down(n), true) => next.aim = state.aim.checked_add(*n)?,
+        (CourseDirection::Down(n), false) => next.depth = state.depth.checked_add(*n)?,
+        (CourseDirection::Forward(n), _) => {
+            next.horizontal = state.horizontal.checked_add(*n)?;
+            if with_aim {
+                next.depth = state.depth.checked_add(state.aim.checked_mul(*n)?)?;
+            }
+        }
+    }
+    Some(next)
+}
+
+// None when no command type is left to pick, as forward moves have used up all the room before the target
+fn next_command(rng: &mut SeededRng, options: &GeneratorOptions, state: &NavigationState, horizontal_limit: Option<i32>) -> Option<CourseDirection> {
+    let forward_room = horizontal_limit.map(|limit| limit - state.horizontal);
+    let mix = if forward_room.is_some_and(|room| room <= 0) {
+        CommandMix { forward: 0, ..options.mix }
+    } else {
+        options.mix
+    };
+    if mix.total() == 0 {
+        return None;
+    }
+
+    let op = match mix.pick(rng) {
+        Command::Forward => {
+            let mut n = options.forward_magnitude.sample(rng);
+            if let Some(room) = forward_room {
+                n = n.min(room);
+            }
+            if options.with_aim {
+                // Moving forward at this aim for longer would leave the allowed depths, so level out if there's
+                // no room at all
+                let depth_room = if state.aim < 0 && options.non_negative_depth {
+                    Some(state.depth / -state.aim)
+                } else if state.aim > 0 {
+                    options.max_depth.map(|max_depth| (max_depth - state.depth) / state.aim)
+                } else {
+                    None
+                };
+                match depth_room {
+                    Some(0) if state.aim < 0 => return Some(CourseDirection::Down(-state.aim)),
+                    Some(0) => return Some(CourseDirection::Up(state.aim)),
+                    Some(room) => n = n.min(room),
+                    None => {}
+                }
+            }
+            CourseDirection::Forward(n)
+        },
+        Command::Up => {
+            let n = options.depth_magnitude.sample(rng);
+            if options.non_negative_depth && !options.with_aim && n > state.depth {
+                if state.depth == 0 {
+                    down_within_limit(options, state, n)
+                } else {
+                    CourseDirection::Up(state.depth)
+                }
+            } else {
+                CourseDirection::Up(n)
+            }
+        },
+        Command::Down => down_within_limit(options, state, options.depth_magnitude.sample(rng))
+    };
+    Some(op)
+}
+
+// Under the simple model a down command can't pass the deepest allowed depth. At that depth it turns upwards
+// instead, which is always possible as the deepest allowed depth is never above the surface.
+fn down_within_limit(options: &GeneratorOptions, state: &NavigationState, n: i32) -> CourseDirection {
+    match options.max_depth.filter(|_| !options.with_aim) {
+        Some(max_depth) if state.depth >= max_depth => CourseDirection::Up(n.min(state.depth)),
+        Some(max_depth) => CourseDirection::Down(n.min(max_depth - state.depth)),
+        None => CourseDirection::Down(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::course::CourseDirection;
+    use crate::navigation::{navigate, trajectory, NavigationState, SubCoordinates};
+    use super::generate_course;
+    use super::parse_command_mix;
+    use super::parse_magnitude;
+    use super::CommandMix;
+    use super::GeneratorError;
+    use super::GeneratorOptions;
+    use super::Magnitude;
+
+    #[test]
+    fn generate_course_is_reproducible_from_seed() {
+        // arrange
+        let options = GeneratorOptions { seed: 42, commands: 200, ..GeneratorOptions::default() };
+        // act
+        let first = generate_course(&options).unwrap();
+        let second = generate_course(&options).unwrap();
+        let other_seed = generate_course(&GeneratorOptions { seed: 43, ..options }).unwrap();
+        // assert
+        assert_eq!(200, first.len());
+        assert_eq!(first, second);
+        assert_ne!(first, other_seed);
+    }
+
+    #[test]
+    fn generate_course_keeps_depth_non_negative_under_either_model() {
+        for with_aim in [false, true] {
+            // arrange
+            let options = GeneratorOptions {
+                seed: 7,
+                commands: 2000,
+                mix: CommandMix { forward: 3, up: 5, down: 2 },
+                forward_magnitude: Magnitude::Geometric(6.0),
+                with_aim,
+                ..GeneratorOptions::default()
+            };
+            // act
+            let course = generate_course(&options).unwrap();
+            let states = trajectory(&course, with_aim, NavigationState::default());
+            // assert
+            assert!(states.iter().all(|s| s.depth >= 0));
+        }
+    }
+
+    #[test]
+    fn generate_course_finishes_at_target() {
+        for with_aim in [false, true] {
+            // arrange
+            let target = SubCoordinates::new(250, 400);
+            let options = GeneratorOptions { seed: 3, commands: 500, with_aim, target: Some(target), ..GeneratorOptions::default() };
+            // act
+            let course = generate_course(&options).unwrap();
+            let states = trajectory(&course, with_aim, NavigationState::default());
+            // assert
+            assert_eq!(target, navigate(&course, with_aim));
+            assert!(states.iter().all(|s| s.depth >= 0));
+        }
+    }
+
+    #[test]
+    fn generate_course_stays_above_max_depth_under_either_model() {
+        for with_aim in [false, true] {
+            // arrange
+            let options = GeneratorOptions {
+                seed: 11,
+                commands: 5000,
+                mix: CommandMix { forward: 3, up: 1, down: 6 },
+                with_aim,
+                max_depth: Some(300),
+                target: Some(SubCoordinates::new(120, 20000)),
+                ..GeneratorOptions::default()
+            };
+            // act
+            let course = generate_course(&options).unwrap();
+            let states = trajectory(&course, with_aim, NavigationState::default());
+            // assert
+            assert!(states.iter().all(|s| (0..=300).contains(&s.depth)));
+            assert_eq!(SubCoordinates::new(120, 20000), navigate(&course, with_aim));
+        }
+    }
+
+    #[test]
+    fn generate_course_stays_in_range_under_aim_without_max_depth() {
+        // arrange
+        let options = GeneratorOptions { seed: 1, commands: 200_000, with_aim: true, ..GeneratorOptions::default() };
+        // act
+        let course = generate_course(&options).unwrap();
+        // assert
+        assert_eq!(200_000, course.len());
+        let states = trajectory(&course, true, NavigationState::default());
+        assert!(states.iter().all(|s| s.depth >= 0));
+    }
+
+    #[test]
+    fn generate_course_stays_in_range_with_huge_magnitudes() {
+        for with_aim in [false, true] {
+            // arrange
+            let options = GeneratorOptions {
+                seed: 5,
+                commands: 5000,
+                forward_magnitude: Magnitude::Geometric(1e8),
+                depth_magnitude: Magnitude::Geometric(1e8),
+                with_aim,
+                ..GeneratorOptions::default()
+            };
+            // act
+            let course = generate_course(&options).unwrap();
+            // assert
+            assert!(!course.is_empty());
+            trajectory(&course, with_aim, NavigationState::default());
+        }
+    }
+
+    #[test]
+    fn generate_course_reports_target_out_of_range() {
+        // arrange
+        let target = SubCoordinates::new(i32::MIN, 1000);
+        let options = GeneratorOptions { seed: 9, commands: 50, mix: CommandMix { forward: 1, up: 0, down: 1 }, target: Some(target), ..GeneratorOptions::default() };
+        // act
+        let result = generate_course(&options);
+        // assert
+        assert_eq!(Err(GeneratorError::TargetOutOfRange(target)), result);
+    }
+
+    #[test]
+    fn generate_course_reaches_target_from_above_surface() {
+        // arrange
+        let target = SubCoordinates::new(50, 500);
+        let options = GeneratorOptions {
+            seed: 1,
+            commands: 200,
+            mix: CommandMix { forward: 1, up: 5, down: 1 },
+            non_negative_depth: false,
+            target: Some(target),
+            ..GeneratorOptions::default()
+        };
+        // act
+        let course = generate_course(&options).unwrap();
+        // assert
+        let states = trajectory(&course, false, NavigationState::default());
+        assert!(states.iter().any(|s| s.depth < 0));
+        assert_eq!(target, states.last().unwrap().coordinates());
+    }
+
+    #[test]
+    fn generate_course_follows_command_mix() {
+        // arrange
+        let options = GeneratorOptions { mix: CommandMix { forward: 1, up: 0, down: 0 }, forward_magnitude: Magnitude::Fixed(4), ..GeneratorOptions::default() };
+        // act
+        let course = generate_course(&options).unwrap();
+        // assert
+        assert!(course.iter().all(|&op| op == CourseDirection::Forward(4)));
+    }
+
+    #[test]
+    fn generate_course_rejects_empty_mix() {
+        // arrange
+        let options = GeneratorOptions { mix: CommandMix { forward: 0, up: 0, down: 0 }, ..GeneratorOptions::default() };
+        // act
+        let result = generate_course(&options);
+        // assert
+        assert_eq!(Err(GeneratorError::EmptyCommandMix), result);
+    }
+
+    #[test]
+    fn parse_magnitude_reads_each_distribution() {
+        assert_eq!(Some(Magnitude::Fixed(5)), parse_magnitude("5"));
+        assert_eq!(Some(Magnitude::Uniform(1, 9)), parse_magnitude("1-9"));
+        assert_eq!(Some(Magnitude::Geometric(2.5)), parse_magnitude("~2.5"));
+        assert_eq!(None, parse_magnitude("9-1"));
+        assert_eq!(None, parse_magnitude("~0.5"));
+    }
+
+    #[test]
+    fn parse_command_mix_reads_three_weights() {
+        assert_eq!(Some(CommandMix { forward: 5, up: 2, down: 3 }), parse_command_mix("5,2,3"));
+        assert_eq!(None, parse_command_mix("5,2"));
+    }
+}
@@ -1,3 +1,17 @@
 pub mod navigation;
 pub mod file;
-pub mod course;
\ No newline at end of file
+pub mod course;
+pub mod planner;
+pub mod pilot;
+pub mod obstacle;
+pub mod energy;
+pub mod timed;
+pub mod fleet;
+pub mod render;
+pub mod diff;
+pub mod observer;
+pub mod checkpoint;
+pub mod reconcile;
+pub mod stream;
+pub mod summary;
+pub mod generate;
\ No newline at end of file
@@ -1,40 +1,712 @@
-use clap::{arg, command};
-use advent_of_code_2021_2::file::load_string_txt_to_vector;
-use advent_of_code_2021_2::navigation::navigate;
-use advent_of_code_2021_2::course::parse_course;
+use clap::parser::ValueSource;
+use clap::{arg, command, ArgAction};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use advent_of_code_2021_2::file::{load_number_txt_to_vector, load_string_txt_to_vector};
+use advent_of_code_2021_2::navigation::{navigate, navigate_observed, NavigationState, SubCoordinates};
+use advent_of_code_2021_2::course::{parse_course, write_course, CourseDirection};
+use advent_of_code_2021_2::pilot::{Pilot, PilotCommand};
+use advent_of_code_2021_2::obstacle::{parse_obstacle_map, validate_safe_course, Obstacle};
+use advent_of_code_2021_2::energy::{check_fuel_budget, energy_consumption, parse_energy_model, EnergyModel};
+use advent_of_code_2021_2::timed::{check_time_step, mission_duration, parse_timed_course, position_at, simulate};
+use advent_of_code_2021_2::fleet::{parse_fleet_member_spec, simulate_fleet, FleetMember};
+use advent_of_code_2021_2::render::{render_ascii_map, render_svg};
+use advent_of_code_2021_2::navigation::{navigation_model_name, parse_navigation_model};
+use advent_of_code_2021_2::diff::{diff_courses, merge_courses};
+use advent_of_code_2021_2::observer::{CsvLog, DepthThresholdAlert};
+use advent_of_code_2021_2::checkpoint::{load_checkpoint, save_checkpoint, NavigationRun};
+use advent_of_code_2021_2::stream::{navigate_stream, StreamError};
+use advent_of_code_2021_2::summary::summarize_course;
+use advent_of_code_2021_2::generate::{generate_course, parse_command_mix, parse_magnitude, CommandMix, GeneratorOptions, Magnitude};
+use advent_of_code_2021_2::reconcile::{parse_depth_alignment, reconcile_depths, DepthAlignment};
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Optional checks run on the course before its coordinates are printed
+struct MissionChecks {
+    obstacles: Vec<Obstacle>,
+    energy_model: Option<EnergyModel>,
+    fuel_budget: Option<f64>
+}
+
+// Observers to attach while navigating
+struct ObserverRequest {
+    depth_alert: Option<i32>,
+    // Each model's log is written to <prefix>-<model>.csv
+    csv_log_prefix: Option<String>
+}
+
+// Where to save progress while navigating, and whether to pick up from earlier progress
+struct CheckpointRequest {
+    // Each model's checkpoint is written to <prefix>-<model>.checkpoint
+    prefix: String,
+    interval: usize,
+    resume: bool
+}
+
+enum SummaryFormat {
+    Text,
+    Json
+}
+
+// Pictures and reports of the route to produce once the course is navigated
+struct RenderRequest {
+    svg_filename: Option<String>,
+    ascii_map: bool,
+    summary: Option<SummaryFormat>,
+    // Which navigation models to draw and summarise, as with_aim flags
+    models: Vec<bool>
+}
 
 fn main() {
     let matches = command!()
         .arg(
-            arg!([filename] "The input file containing the course information")
-                .required(true)
+            arg!([filename] "The input file containing the course information, or - to read it from stdin. Give several, as <file>[:<model>[:<depth>,<horizontal>]], to simulate a fleet")
+                .required_unless_present("pilot")
+                .num_args(1..)
                 .index(1)
         )
+        .arg(
+            arg!(--pilot "Steer the submarine interactively, starting from the input file's course if given")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(--obstacles <FILE> "An obstacle map; courses that collide with it are rejected instead of navigated")
+        )
+        .arg(
+            arg!(--"energy-model" <FILE> "Costs for the energy model, one \"<forward|depth|pressure|aim> <cost>\" per line")
+        )
+        .arg(
+            arg!(--"fuel-budget" <AMOUNT> "Reject courses that need more energy than this")
+                .value_parser(clap::value_parser!(f64))
+        )
+        .arg(
+            arg!(--timed "Read the input as a timed course, with lines like \"t=12.5 forward 3 @ speed 1.2\"")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(--"time-step" <STEP> "With --timed, also print the position at every multiple of this time")
+                .value_parser(|step: &str| step.parse::<f64>().map_err(|e| e.to_string()).and_then(|step| check_time_step(step).map_err(|e| e.to_string())))
+        )
+        .arg(
+            arg!(--"min-separation" <DISTANCE> "With several input files, report whenever two submarines are closer than this")
+                .value_parser(clap::value_parser!(f64))
+                .default_value("1")
+        )
+        .arg(
+            arg!(--svg <FILE> "Write an SVG picture of the route to this file")
+        )
+        .arg(
+            arg!(--"ascii-map" "Print a map of the route")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(--route <MODEL> "Only draw or summarise the route under this navigation model (simple or aim), rather than both")
+        )
+        .arg(
+            arg!(--diff <FILE> "Compare the input course with another course file")
+        )
+        .arg(
+            arg!(--merge <FILES> "Merge the input course with THEIRS, both edited from BASE, printing the merged course")
+                .num_args(2)
+                .value_names(["BASE", "THEIRS"])
+        )
+        .arg(
+            arg!(--"depth-alert" <DEPTH> "Report every command that takes the submarine across this depth")
+                .value_parser(clap::value_parser!(i32))
+        )
+        .arg(
+            arg!(--"csv-log" <PREFIX> "Log the state after every command to <PREFIX>-simple.csv and <PREFIX>-aim.csv")
+        )
+        .arg(
+            arg!(--checkpoint <PREFIX> "Save progress to <PREFIX>-simple.checkpoint and <PREFIX>-aim.checkpoint while navigating")
+        )
+        .arg(
+            arg!(--"checkpoint-interval" <COMMANDS> "With --checkpoint, how many commands to run between saves")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("1000000")
+        )
+        .arg(
+            arg!(--resume "With --checkpoint, carry on from the saved checkpoints rather than starting over. Depth alerts and CSV logs then only cover the commands run after resuming.")
+                .action(ArgAction::SetTrue)
+                .requires("checkpoint")
+        )
+        .arg(
+            arg!(--sonar <FILE> "Compare the depths the course passes through with these sonar readings, one depth per line")
+        )
+        .arg(
+            arg!(--align <ALIGNMENT> "With --sonar, pair readings with commands by step index (step) or horizontal position (horizontal)")
+                .default_value("step")
+                .requires("sonar")
+        )
+        .arg(
+            arg!(--tolerance <DEPTH> "With --sonar, how far computed and measured depths may differ before it is reported")
+                .value_parser(clap::value_parser!(f64))
+                .default_value("0")
+        )
+        .arg(
+            arg!(--summary [FORMAT] "Print a mission summary for each navigation model, as text (the default) or json")
+                .value_parser(["text", "json"])
+                .default_missing_value("text")
+        )
+        .arg(
+            arg!(--generate <COMMANDS> "Write a random course of this many commands to the input file, or stdout for -")
+                .value_parser(clap::value_parser!(usize))
+        )
+        .arg(
+            arg!(--seed <SEED> "With --generate, the seed to generate from. Without one a seed is picked and reported.")
+                .value_parser(clap::value_parser!(u64))
+                .requires("generate")
+        )
+        .arg(
+            arg!(--mix <WEIGHTS> "With --generate, relative weights of forward, up and down commands, as <forward>,<up>,<down>")
+                .value_parser(|spec: &str| parse_command_mix(spec).ok_or("expected <forward>,<up>,<down> weights"))
+                .requires("generate")
+        )
+        .arg(
+            arg!(--"forward-size" <MAGNITUDE> "With --generate, forward amounts: <n>, <min>-<max> or ~<mean>")
+                .value_parser(|spec: &str| parse_magnitude(spec).ok_or("expected <n>, <min>-<max> or ~<mean>"))
+                .requires("generate")
+        )
+        .arg(
+            arg!(--"depth-size" <MAGNITUDE> "With --generate, up and down amounts: <n>, <min>-<max> or ~<mean>")
+                .value_parser(|spec: &str| parse_magnitude(spec).ok_or("expected <n>, <min>-<max> or ~<mean>"))
+                .requires("generate")
+        )
+        .arg(
+            arg!(--"generate-model" <MODEL> "With --generate, the navigation model (simple or aim) the depth and target apply under")
+                .default_value("simple")
+        )
+        .arg(
+            arg!(--"allow-negative-depth" "With --generate, let the course rise above the surface")
+                .action(ArgAction::SetTrue)
+                .requires("generate")
+        )
+        .arg(
+            arg!(--"max-depth" <DEPTH> "With --generate, the deepest the course may go")
+                .value_parser(clap::value_parser!(i32))
+                .requires("generate")
+        )
+        .arg(
+            arg!(--target <POSITION> "With --generate, finish the course at <depth>,<horizontal>")
+                .requires("generate")
+        )
+        .arg(
+            arg!(--stream "Follow the course a line at a time rather than loading it all first; implied when reading stdin")
+                .action(ArgAction::SetTrue)
+        )
         .get_matches();
 
-    let filename = matches
-        .get_one::<String>("filename")
-        .expect("Expecting an input file");
+    if matches.get_flag("pilot") {
+        run_pilot(matches.get_one::<String>("filename"));
+        return;
+    }
+
+    let filenames: Vec<&String> = matches
+        .get_many::<String>("filename")
+        .expect("Expecting an input file")
+        .collect();
+    if let Some(&commands) = matches.get_one::<usize>("generate") {
+        let model_name = matches.get_one::<String>("generate-model").expect("Model has a default");
+        let Some(with_aim) = parse_navigation_model(model_name) else {
+            println!("Unknown navigation model \"{}\", expected simple or aim", model_name);
+            return;
+        };
+        let target = match matches.get_one::<String>("target") {
+            Some(target) => match parse_target(target) {
+                Some(target) => Some(target),
+                None => {
+                    println!("Invalid target \"{}\", expected <depth>,<horizontal>", target);
+                    return;
+                }
+            },
+            None => None
+        };
+        let defaults = GeneratorOptions::default();
+        let options = GeneratorOptions {
+            seed: matches.get_one::<u64>("seed").copied().unwrap_or_else(clock_seed),
+            commands,
+            mix: matches.get_one::<CommandMix>("mix").copied().unwrap_or(defaults.mix),
+            forward_magnitude: matches.get_one::<Magnitude>("forward-size").copied().unwrap_or(defaults.forward_magnitude),
+            depth_magnitude: matches.get_one::<Magnitude>("depth-size").copied().unwrap_or(defaults.depth_magnitude),
+            with_aim,
+            non_negative_depth: !matches.get_flag("allow-negative-depth"),
+            max_depth: matches.get_one::<i32>("max-depth").copied(),
+            target
+        };
+        run_generate(filenames[0], &options);
+        return;
+    }
+
+    if filenames.len() > 1 {
+        // These all apply to a single course, and a fleet would otherwise quietly ignore them
+        let single_course_options = ["obstacles", "energy-model", "fuel-budget", "timed", "time-step", "svg", "ascii-map", "route", "diff", "merge", "depth-alert", "csv-log", "checkpoint", "checkpoint-interval", "resume", "sonar", "align", "tolerance", "summary", "stream"];
+        if let Some(option) = single_course_options.iter().find(|&&id| matches.value_source(id) == Some(ValueSource::CommandLine)) {
+            println!("--{} applies to a single course, so can't be used with a fleet", option);
+            return;
+        }
+        let min_separation = *matches.get_one::<f64>("min-separation").expect("Separation has a default");
+        run_fleet(&filenames, min_separation);
+        return;
+    }
+    let filename = filenames[0];
+
+    if matches.get_flag("timed") {
+        if let Some(course) = load_and_parse(filename, "timed course", parse_timed_course) {
+            println!("Mission duration: {}", mission_duration(&course));
+            for (with_aim, label) in [(false, "without aim"), (true, "with aim")] {
+                if let Some(&time_step) = matches.get_one::<f64>("time-step") {
+                    for sample in simulate(&course, with_aim, time_step).expect("Time step was checked when parsed") {
+                        println!("Position ({}) at {}", label, sample);
+                    }
+                }
+                println!("Final position ({}): {}", label, position_at(&course, with_aim, mission_duration(&course)));
+            }
+        }
+        return;
+    }
+
+    if let Some(other_filename) = matches.get_one::<String>("diff") {
+        run_diff(filename, other_filename);
+        return;
+    }
+
+    if let Some(mut merge_filenames) = matches.get_many::<String>("merge") {
+        let base_filename = merge_filenames.next().expect("Merge takes two files");
+        let theirs_filename = merge_filenames.next().expect("Merge takes two files");
+        run_merge(base_filename, filename, theirs_filename);
+        return;
+    }
+
+    if filename == "-" || matches.get_flag("stream") {
+        // These all need the whole course at once
+        let whole_course_options = ["obstacles", "energy-model", "fuel-budget", "svg", "ascii-map", "depth-alert", "csv-log", "checkpoint", "sonar", "summary"];
+        match whole_course_options.iter().find(|&&id| matches.value_source(id) == Some(ValueSource::CommandLine)) {
+            Some(option) => println!("--{} needs the whole course, so can't be used while streaming", option),
+            None => run_stream(filename)
+        }
+        return;
+    }
+
+    if let Some(sonar_filename) = matches.get_one::<String>("sonar") {
+        let alignment_name = matches.get_one::<String>("align").expect("Alignment has a default");
+        let Some(alignment) = parse_depth_alignment(alignment_name) else {
+            println!("Unknown alignment \"{}\", expected step or horizontal", alignment_name);
+            return;
+        };
+        let tolerance = *matches.get_one::<f64>("tolerance").expect("Tolerance has a default");
+        run_reconcile(filename, sonar_filename, alignment, tolerance);
+        return;
+    }
+
+    let obstacles = match matches.get_one::<String>("obstacles") {
+        Some(obstacle_filename) => match load_and_parse(obstacle_filename, "obstacle map", parse_obstacle_map) {
+            Some(obstacles) => obstacles,
+            None => return
+        },
+        None => Vec::new()
+    };
+
+    let energy_model = match matches.get_one::<String>("energy-model") {
+        Some(model_filename) => match load_and_parse(model_filename, "energy model", parse_energy_model) {
+            Some(model) => Some(model),
+            None => return
+        },
+        None => None
+    };
+
+    let checks = MissionChecks {
+        obstacles,
+        energy_model,
+        fuel_budget: matches.get_one::<f64>("fuel-budget").copied()
+    };
+
+    let models = match matches.get_one::<String>("route") {
+        Some(name) => match parse_navigation_model(name) {
+            Some(with_aim) => vec![with_aim],
+            None => {
+                println!("Unknown navigation model \"{}\", expected simple or aim", name);
+                return;
+            }
+        },
+        None => vec![false, true]
+    };
+    let render = RenderRequest {
+        svg_filename: matches.get_one::<String>("svg").cloned(),
+        ascii_map: matches.get_flag("ascii-map"),
+        summary: matches.get_one::<String>("summary").map(|format| match format.as_str() {
+            "json" => SummaryFormat::Json,
+            _ => SummaryFormat::Text
+        }),
+        models
+    };
+    let observers = ObserverRequest {
+        depth_alert: matches.get_one::<i32>("depth-alert").copied(),
+        csv_log_prefix: matches.get_one::<String>("csv-log").cloned()
+    };
+
+    let checkpoints = matches.get_one::<String>("checkpoint").map(|prefix| CheckpointRequest {
+        prefix: prefix.to_string(),
+        interval: *matches.get_one::<usize>("checkpoint-interval").expect("Interval has a default"),
+        resume: matches.get_flag("resume")
+    });
 
-    match load_string_txt_to_vector(&filename) {
+    match load_string_txt_to_vector(filename) {
         Ok(lines) => {
-            process_input_lines(&lines);
+            process_input_lines(&lines, &checks, &observers, checkpoints.as_ref(), &render);
         },
         Err(e) => {
-            println!("Failed to load file: {}", e.to_string());
+            println!("Failed to load file: {}", e);
         }
     }
 }
 
-fn process_input_lines(lines: &Vec<String>) {
+fn load_and_parse<T, E: Display>(filename: &String, description: &str, parse: fn(&[String]) -> Result<T, E>) -> Option<T> {
+    match load_string_txt_to_vector(filename) {
+        Ok(lines) => match parse(&lines) {
+            Ok(parsed) => Some(parsed),
+            Err(e) => {
+                println!("Failed to parse {}: {}", description, e);
+                None
+            }
+        },
+        Err(e) => {
+            println!("Failed to load {}: {}", description, e);
+            None
+        }
+    }
+}
+
+fn process_input_lines(lines: &[String], checks: &MissionChecks, observers: &ObserverRequest, checkpoints: Option<&CheckpointRequest>, render: &RenderRequest) {
     match parse_course(lines) {
         Ok(parsed_lines) => {
-            println!("Calculated coordinates (without aim): {}", navigate(&parsed_lines, false).to_string());
-            println!("Calculated coordinates (with aim): {}", navigate(&parsed_lines, true).to_string());
+            for (with_aim, label) in [(false, "without aim"), (true, "with aim")] {
+                if !passes_checks(&parsed_lines, with_aim, label, checks) {
+                    continue;
+                }
+                let coordinates = match checkpoints {
+                    Some(request) => navigate_with_checkpoints(&parsed_lines, with_aim, label, request, observers),
+                    None => Some(navigate_with_observers(&parsed_lines, with_aim, label, observers))
+                };
+                if let Some(coordinates) = coordinates {
+                    println!("Calculated coordinates ({}): {}", label, coordinates);
+                }
+            }
+            render_route(&parsed_lines, render);
+        },
+        Err(e) => {
+            println!("Failed to parse course: {}", e);
+        }
+    }
+}
+
+// Prints the same as process_input_lines does when no checks, observers or pictures are requested
+fn run_stream(filename: &String) {
+    let models = [(false, "without aim"), (true, "with aim")];
+    let with_aim_models: Vec<bool> = models.iter().map(|&(with_aim, _)| with_aim).collect();
+    let result = if filename == "-" {
+        navigate_stream(io::stdin().lock(), &with_aim_models)
+    } else {
+        match File::open(filename) {
+            Ok(file) => navigate_stream(BufReader::new(file), &with_aim_models),
+            Err(e) => {
+                println!("Failed to load file: {}", e);
+                return;
+            }
+        }
+    };
+
+    match result {
+        Ok(states) => {
+            for ((_, label), state) in models.iter().zip(states.iter()) {
+                println!("Calculated coordinates ({}): {}", label, state.coordinates());
+            }
+        },
+        Err(e @ StreamError::Io(..)) => println!("Failed to load file: {}", e),
+        Err(StreamError::Parse(e)) => println!("Failed to parse course: {}", e)
+    }
+}
+
+fn parse_target(target: &str) -> Option<SubCoordinates> {
+    let (depth, horizontal) = target.split_once(',')?;
+    Some(SubCoordinates::new(depth.trim().parse().ok()?, horizontal.trim().parse().ok()?))
+}
+
+fn clock_seed() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_nanos() as u64).unwrap_or(0)
+}
+
+fn run_generate(filename: &String, options: &GeneratorOptions) {
+    // The seed goes to stderr so it can't end up in a course written to stdout
+    eprintln!("Generating with seed {}", options.seed);
+    let course = match generate_course(options) {
+        Ok(course) => course,
+        Err(e) => {
+            println!("Failed to generate course: {}", e);
+            return;
+        }
+    };
+
+    let result = if filename == "-" {
+        write_course(&mut io::stdout().lock(), &course)
+    } else {
+        File::create(filename).and_then(|file| write_course(&mut BufWriter::new(file), &course))
+    };
+    if let Err(e) = result {
+        println!("Failed to write course: {}", e);
+    }
+}
+
+fn run_pilot(filename: Option<&String>) {
+    let mut pilot = Pilot::new(false);
+    if let Some(filename) = filename {
+        if let Err(e) = pilot.apply(PilotCommand::Load(filename.to_string())) {
+            println!("Failed to load course: {}", e);
+        }
+    }
+
+    println!("Enter course directions, or undo, reset, save <file>, load <file>, model <simple|aim>, quit");
+    println!("{}", pilot);
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush().expect("Failed to flush prompt");
+
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {},
+            Err(e) => {
+                println!("Failed to read input: {}", e);
+                break;
+            }
+        }
+
+        match line.trim() {
+            "" => continue,
+            "quit" | "exit" => break,
+            command => match pilot.execute(command) {
+                Ok(()) => println!("{}", pilot),
+                Err(e) => println!("{}", e)
+            }
+        }
+    }
+}
+
+fn passes_checks(course: &[CourseDirection], with_aim: bool, label: &str, checks: &MissionChecks) -> bool {
+    if let Err(collisions) = validate_safe_course(course, with_aim, &checks.obstacles) {
+        println!("Course is unsafe ({}):", label);
+        for collision in collisions {
+            println!("  {}", collision);
+        }
+        return false;
+    }
+
+    let energy_model = checks.energy_model.unwrap_or_default();
+    match checks.fuel_budget {
+        Some(budget) => match check_fuel_budget(course, with_aim, &energy_model, budget) {
+            Ok(report) => println!("Energy used ({}): {}", label, report.total),
+            Err(exhausted) => {
+                println!("Course exceeds fuel budget ({}): {}", label, exhausted);
+                return false;
+            }
+        },
+        None => if checks.energy_model.is_some() {
+            println!("Energy used ({}): {}", label, energy_consumption(course, with_aim, &energy_model).total);
+        }
+    }
+
+    true
+}
+
+fn run_fleet(specs: &[&String], min_separation: f64) {
+    let mut fleet = Vec::new();
+    for spec in specs {
+        let (filename, with_aim, start) = match parse_fleet_member_spec(spec) {
+            Ok(member) => member,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        match load_and_parse(&filename, "course", parse_course) {
+            Some(course) => fleet.push(FleetMember { name: spec.to_string(), course, start, with_aim }),
+            None => return
+        }
+    }
+
+    let report = simulate_fleet(&fleet, min_separation);
+    for event in report.events.iter() {
+        println!("Tick {}: {} and {} are {} apart", event.tick, fleet[event.first].name, fleet[event.second].name, event.distance);
+    }
+    for (member, coordinates) in fleet.iter().zip(report.final_coordinates.iter()) {
+        println!("Final coordinates for {}: {}", member.name, coordinates);
+    }
+}
+
+fn render_route(course: &[CourseDirection], render: &RenderRequest) {
+    if let Some(svg_filename) = &render.svg_filename {
+        if let Err(e) = fs::write(svg_filename, render_svg(course, &render.models, 800.0, 600.0)) {
+            println!("Failed to write SVG: {}", e);
+        }
+    }
+
+    if render.ascii_map {
+        for &with_aim in render.models.iter() {
+            println!("Route ({} model):", navigation_model_name(with_aim));
+            println!("{}", render_ascii_map(course, with_aim, 72, 24));
+        }
+    }
+
+    match render.summary {
+        Some(SummaryFormat::Text) => {
+            for &with_aim in render.models.iter() {
+                println!("Mission summary:\n{}", summarize_course(course, with_aim));
+            }
+        },
+        Some(SummaryFormat::Json) => {
+            let summaries: Vec<String> = render.models.iter().map(|&with_aim| summarize_course(course, with_aim).to_json()).collect();
+            println!("[{}]", summaries.join(","));
         },
-        Err(_) => {
-            // TODO: More nuanced handling
-            println!("Failed to parse lines");
+        None => {}
+    }
+}
+
+fn run_diff(old_filename: &String, new_filename: &String) {
+    let (Some(old), Some(new)) = (load_and_parse(old_filename, "course", parse_course), load_and_parse(new_filename, "course", parse_course)) else {
+        return;
+    };
+
+    let diff = diff_courses(&old, &new);
+    for op in diff.ops.iter() {
+        println!("{}", op);
+    }
+    for model in diff.models.iter() {
+        println!(
+            "Final coordinates ({} model): {} -> {} (depth {:+}, horizontal {:+})",
+            navigation_model_name(model.with_aim), model.old, model.new, model.depth_change(), model.horizontal_change()
+        );
+    }
+}
+
+fn run_merge(base_filename: &String, ours_filename: &String, theirs_filename: &String) {
+    let (Some(base), Some(ours), Some(theirs)) = (
+        load_and_parse(base_filename, "course", parse_course),
+        load_and_parse(ours_filename, "course", parse_course),
+        load_and_parse(theirs_filename, "course", parse_course)
+    ) else {
+        return;
+    };
+
+    match merge_courses(&base, &ours, &theirs) {
+        Ok(merged) => {
+            for direction in merged {
+                println!("{}", direction);
+            }
+        },
+        Err(conflicts) => {
+            println!("Merge failed with {} conflict(s):", conflicts.len());
+            for conflict in conflicts {
+                println!("  {}", conflict);
+            }
+        }
+    }
+}
+
+fn run_reconcile(course_filename: &String, sonar_filename: &String, alignment: DepthAlignment, tolerance: f64) {
+    let Some(course) = load_and_parse(course_filename, "course", parse_course) else {
+        return;
+    };
+    let measured = match load_number_txt_to_vector(sonar_filename) {
+        Ok(measured) => measured,
+        Err(e) => {
+            println!("Failed to load sonar readings: {}", e);
+            return;
+        }
+    };
+
+    let position_name = match alignment {
+        DepthAlignment::StepIndex => "Step",
+        DepthAlignment::Horizontal => "Horizontal"
+    };
+    for (with_aim, label) in [(false, "without aim"), (true, "with aim")] {
+        let reconciliation = reconcile_depths(&course, with_aim, &measured, alignment, tolerance);
+        let disagreements = reconciliation.disagreements();
+        println!("Compared {} depths ({}), {} disagree", reconciliation.comparisons.len(), label, disagreements.len());
+        for comparison in disagreements {
+            println!(
+                "  {} {}: computed {}, measured {} ({:+})",
+                position_name, comparison.position, comparison.computed, comparison.measured, comparison.difference()
+            );
+        }
+        let fit = reconciliation.fit;
+        println!("  Best offset: {:+.3} (RMS error {:.3})", fit.offset, fit.offset_rms_error);
+        println!("  Best scale: {:.3} (RMS error {:.3})", fit.scale, fit.scale_rms_error);
+    }
+}
+
+fn navigate_with_observers(course: &[CourseDirection], with_aim: bool, label: &str, request: &ObserverRequest) -> SubCoordinates {
+    if request.depth_alert.is_none() && request.csv_log_prefix.is_none() {
+        return navigate(course, with_aim);
+    }
+
+    let mut observers = open_observers(with_aim, request);
+    let final_state = navigate_observed(course, with_aim, NavigationState::default(), &mut observers);
+    report_observers(observers, label);
+
+    final_state.coordinates()
+}
+
+// The requested observers for one navigation model, as a pair that navigation can notify together
+fn open_observers(with_aim: bool, request: &ObserverRequest) -> (Option<DepthThresholdAlert>, Option<CsvLog<BufWriter<File>>>) {
+    let csv_log = match &request.csv_log_prefix {
+        Some(prefix) => {
+            let csv_filename = format!("{}-{}.csv", prefix, navigation_model_name(with_aim));
+            match File::create(&csv_filename) {
+                Ok(file) => Some(CsvLog::new(BufWriter::new(file))),
+                Err(e) => {
+                    println!("Failed to create CSV log {}: {}", csv_filename, e);
+                    None
+                }
+            }
+        },
+        None => None
+    };
+
+    (request.depth_alert.map(DepthThresholdAlert::new), csv_log)
+}
+
+fn report_observers((depth_alert, csv_log): (Option<DepthThresholdAlert>, Option<CsvLog<BufWriter<File>>>), label: &str) {
+    for alert in depth_alert.iter().flat_map(|a| a.alerts.iter()) {
+        let direction = if alert.descending() { "descends" } else { "ascends" };
+        println!("Depth alert ({}): command {} {} from {} to {}", label, alert.index + 1, direction, alert.from_depth, alert.to_depth);
+    }
+    if let Some(Err(e)) = csv_log.map(|log| log.finish()) {
+        println!("Failed to write CSV log: {}", e);
+    }
+}
+
+fn navigate_with_checkpoints(course: &[CourseDirection], with_aim: bool, label: &str, request: &CheckpointRequest, observers: &ObserverRequest) -> Option<SubCoordinates> {
+    let path = format!("{}-{}.checkpoint", request.prefix, navigation_model_name(with_aim));
+    let run = if request.resume {
+        load_checkpoint(&path).and_then(|checkpoint| NavigationRun::resume(&checkpoint, course, with_aim))
+    } else {
+        Ok(NavigationRun::new(with_aim))
+    };
+
+    let mut observers = open_observers(with_aim, observers);
+    let result = run.and_then(|mut run| {
+        let final_state = run.run(course, request.interval, &mut observers, |checkpoint| save_checkpoint(&path, checkpoint))?;
+        save_checkpoint(&path, &run.checkpoint())?;
+        Ok(final_state)
+    });
+    report_observers(observers, label);
+
+    match result {
+        Ok(final_state) => Some(final_state.coordinates()),
+        Err(e) => {
+            println!("Checkpointed navigation using {} failed: {}", path, e);
+            None
         }
     }
 }
\ No newline at end of file
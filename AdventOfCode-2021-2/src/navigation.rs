@@ -1,54 +1,133 @@
 use crate::course::CourseDirection;
+use crate::observer::NavigationObserver;
 use std::fmt;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
 pub struct SubCoordinates {
     depth: i32,
     horizontal: i32
 }
 
+impl SubCoordinates {
+    pub fn new(depth: i32, horizontal: i32) -> Self {
+        Self { depth, horizontal }
+    }
+
+    pub fn depth(&self) -> i32 {
+        self.depth
+    }
+
+    pub fn horizontal(&self) -> i32 {
+        self.horizontal
+    }
+}
+
 impl fmt::Display for SubCoordinates {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "Depth: {}, Horizontal: {}", self.depth, self.horizontal)
     }
 }
 
-pub fn navigate(operations: &Vec<CourseDirection>, with_aim: bool) -> SubCoordinates {
-    let mut d: i32 = 0;
-    let mut h: i32 = 0;
-    let mut aim: i32 = 0;
-    for op in operations.iter() {
+// The full state of the submarine part way through a course. Aim is only meaningful when navigating with aim.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct NavigationState {
+    pub depth: i32,
+    pub horizontal: i32,
+    pub aim: i32
+}
+
+impl NavigationState {
+    pub fn coordinates(&self) -> SubCoordinates {
+        SubCoordinates::new(self.depth, self.horizontal)
+    }
+
+    // Advances the state by a single course direction
+    pub fn apply(&mut self, op: &CourseDirection, with_aim: bool) {
         match op {
             CourseDirection::Up(n) => {
                 if with_aim {
-                    aim -= n;
+                    self.aim -= n;
                 } else {
-                    d -= n;
+                    self.depth -= n;
                 }
             },
             CourseDirection::Down(n) => {
                 if with_aim {
-                    aim += n;
+                    self.aim += n;
                 } else {
-                    d += n;
+                    self.depth += n;
                 }
             },
             CourseDirection::Forward(n) => {
-                h += n;
+                self.horizontal += n;
                 if with_aim {
-                    d += aim * n;
+                    self.depth += self.aim * n;
                 }
             }
         }
     }
+}
+
+impl fmt::Display for NavigationState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Depth: {}, Horizontal: {}, Aim: {}", self.depth, self.horizontal, self.aim)
+    }
+}
+
+pub fn navigate(operations: &[CourseDirection], with_aim: bool) -> SubCoordinates {
+    navigate_from(operations, with_aim, NavigationState::default()).coordinates()
+}
+
+pub fn navigate_from(operations: &[CourseDirection], with_aim: bool, start: NavigationState) -> NavigationState {
+    navigate_observed(operations, with_aim, start, &mut ())
+}
+
+pub fn navigate_observed<O: NavigationObserver>(operations: &[CourseDirection], with_aim: bool, start: NavigationState, observer: &mut O) -> NavigationState {
+    let mut state = start;
+    for (index, op) in operations.iter().enumerate() {
+        observer.before_step(index, op, &state);
+        state.apply(op, with_aim);
+        observer.after_step(index, op, &state);
+    }
+
+    state
+}
+
+// Every state the submarine passes through: the start, then the state after each operation.
+// Consecutive states bound the straight-line segment travelled by one operation.
+pub fn trajectory(operations: &[CourseDirection], with_aim: bool, start: NavigationState) -> Vec<NavigationState> {
+    let mut state = start;
+    let mut states = Vec::with_capacity(operations.len() + 1);
+    states.push(state);
+    for op in operations.iter() {
+        state.apply(op, with_aim);
+        states.push(state);
+    }
 
-    SubCoordinates { depth: d, horizontal: h }
+    states
+}
+
+// Maps a navigation model's name onto the with_aim flag used throughout navigation
+pub fn parse_navigation_model(name: &str) -> Option<bool> {
+    match name {
+        "simple" => Some(false),
+        "aim" => Some(true),
+        _ => None
+    }
+}
+
+pub fn navigation_model_name(with_aim: bool) -> &'static str {
+    if with_aim { "aim" } else { "simple" }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::course::CourseDirection;
     use super::navigate;
+    use super::navigate_from;
+    use super::parse_navigation_model;
+    use super::trajectory;
+    use super::NavigationState;
     use super::SubCoordinates;
 
     #[test]
@@ -88,4 +167,53 @@ mod tests {
         // assert 
         assert_eq!(result, SubCoordinates { depth: 60, horizontal: 15 });
     }
+
+    #[test]
+    fn navigate_from_continues_from_start_state() {
+        // arrange
+        let course = vec![
+            CourseDirection::Forward(2),
+            CourseDirection::Up(1),
+            CourseDirection::Forward(3),
+        ];
+        let start = NavigationState { depth: 10, horizontal: 4, aim: 2 };
+
+        // act
+        let result = navigate_from(&course, true, start);
+
+        // assert
+        assert_eq!(result, NavigationState { depth: 17, horizontal: 9, aim: 1 });
+    }
+
+    #[test]
+    fn navigation_state_apply_steps_one_direction() {
+        // arrange
+        let mut state = NavigationState { depth: 4, horizontal: 1, aim: 3 };
+        // act
+        state.apply(&CourseDirection::Forward(2), true);
+        state.apply(&CourseDirection::Up(5), true);
+        // assert
+        assert_eq!(state, NavigationState { depth: 10, horizontal: 3, aim: -2 });
+    }
+
+    #[test]
+    fn parse_navigation_model_recognises_names() {
+        assert_eq!(Some(false), parse_navigation_model("simple"));
+        assert_eq!(Some(true), parse_navigation_model("aim"));
+        assert_eq!(None, parse_navigation_model("sideways"));
+    }
+
+    #[test]
+    fn trajectory_records_every_state() {
+        // arrange
+        let course = vec![CourseDirection::Down(2), CourseDirection::Forward(3)];
+        // act
+        let result = trajectory(&course, true, NavigationState::default());
+        // assert
+        assert_eq!(result, vec![
+            NavigationState { depth: 0, horizontal: 0, aim: 0 },
+            NavigationState { depth: 0, horizontal: 0, aim: 2 },
+            NavigationState { depth: 6, horizontal: 3, aim: 2 },
+        ]);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,222 @@
+use crate::course::CourseDirection;
+use crate::navigation::NavigationState;
+use std::io::{self, Write};
+
+// Receives every step of a navigation run. Both methods default to doing nothing, so observers only implement what
+// they need. Navigation is generic over the observer, so the unit observer () compiles away entirely.
+pub trait NavigationObserver {
+    // Called with the state before the command at this index is applied
+    fn before_step(&mut self, _index: usize, _op: &CourseDirection, _state: &NavigationState) {}
+    // Called with the state after the command at this index is applied
+    fn after_step(&mut self, _index: usize, _op: &CourseDirection, _state: &NavigationState) {}
+}
+
+impl NavigationObserver for () {}
+
+impl<T: NavigationObserver + ?Sized> NavigationObserver for &mut T {
+    fn before_step(&mut self, index: usize, op: &CourseDirection, state: &NavigationState) {
+        (**self).before_step(index, op, state);
+    }
+
+    fn after_step(&mut self, index: usize, op: &CourseDirection, state: &NavigationState) {
+        (**self).after_step(index, op, state);
+    }
+}
+
+impl<T: NavigationObserver + ?Sized> NavigationObserver for Box<T> {
+    fn before_step(&mut self, index: usize, op: &CourseDirection, state: &NavigationState) {
+        (**self).before_step(index, op, state);
+    }
+
+    fn after_step(&mut self, index: usize, op: &CourseDirection, state: &NavigationState) {
+        (**self).after_step(index, op, state);
+    }
+}
+
+impl<T: NavigationObserver> NavigationObserver for Option<T> {
+    fn before_step(&mut self, index: usize, op: &CourseDirection, state: &NavigationState) {
+        if let Some(observer) = self {
+            observer.before_step(index, op, state);
+        }
+    }
+
+    fn after_step(&mut self, index: usize, op: &CourseDirection, state: &NavigationState) {
+        if let Some(observer) = self {
+            observer.after_step(index, op, state);
+        }
+    }
+}
+
+// Pairs notify the first observer, then the second, so any number of observers can be nested together
+impl<A: NavigationObserver, B: NavigationObserver> NavigationObserver for (A, B) {
+    fn before_step(&mut self, index: usize, op: &CourseDirection, state: &NavigationState) {
+        self.0.before_step(index, op, state);
+        self.1.before_step(index, op, state);
+    }
+
+    fn after_step(&mut self, index: usize, op: &CourseDirection, state: &NavigationState) {
+        self.0.after_step(index, op, state);
+        self.1.after_step(index, op, state);
+    }
+}
+
+impl<T: NavigationObserver> NavigationObserver for Vec<T> {
+    fn before_step(&mut self, index: usize, op: &CourseDirection, state: &NavigationState) {
+        for observer in self.iter_mut() {
+            observer.before_step(index, op, state);
+        }
+    }
+
+    fn after_step(&mut self, index: usize, op: &CourseDirection, state: &NavigationState) {
+        for observer in self.iter_mut() {
+            observer.after_step(index, op, state);
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DepthAlert {
+    // Index of the command that crossed the threshold
+    pub index: usize,
+    pub from_depth: i32,
+    pub to_depth: i32
+}
+
+impl DepthAlert {
+    pub fn descending(&self) -> bool {
+        self.to_depth > self.from_depth
+    }
+}
+
+// Records each command that takes the submarine from one side of a depth threshold to the other.
+// Reaching the threshold exactly counts as being below it.
+pub struct DepthThresholdAlert {
+    threshold: i32,
+    previous_depth: i32,
+    pub alerts: Vec<DepthAlert>
+}
+
+impl DepthThresholdAlert {
+    pub fn new(threshold: i32) -> Self {
+        Self { threshold, previous_depth: 0, alerts: Vec::new() }
+    }
+}
+
+impl NavigationObserver for DepthThresholdAlert {
+    fn before_step(&mut self, _index: usize, _op: &CourseDirection, state: &NavigationState) {
+        self.previous_depth = state.depth;
+    }
+
+    fn after_step(&mut self, index: usize, _op: &CourseDirection, state: &NavigationState) {
+        if (self.previous_depth >= self.threshold) != (state.depth >= self.threshold) {
+            self.alerts.push(DepthAlert { index, from_depth: self.previous_depth, to_depth: state.depth });
+        }
+    }
+}
+
+// Writes one CSV row per command with the state after it. Write failures stop logging, and are reported by finish.
+pub struct CsvLog<W: Write> {
+    writer: W,
+    error: Option<io::Error>
+}
+
+impl<W: Write> CsvLog<W> {
+    pub fn new(mut writer: W) -> Self {
+        let error = writeln!(writer, "index,command,depth,horizontal,aim").err();
+        Self { writer, error }
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.error {
+            Some(e) => Err(e),
+            None => self.writer.flush().map(|_| self.writer)
+        }
+    }
+}
+
+impl<W: Write> NavigationObserver for CsvLog<W> {
+    fn after_step(&mut self, index: usize, op: &CourseDirection, state: &NavigationState) {
+        if self.error.is_none() {
+            self.error = writeln!(self.writer, "{},{},{},{},{}", index, op, state.depth, state.horizontal, state.aim).err();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::course::CourseDirection;
+    use crate::navigation::{navigate_observed, NavigationState};
+    use super::CsvLog;
+    use super::DepthAlert;
+    use super::DepthThresholdAlert;
+    use super::NavigationObserver;
+
+    fn example_course() -> Vec<CourseDirection> {
+        vec![
+            CourseDirection::Forward(5),
+            CourseDirection::Down(5),
+            CourseDirection::Forward(8),
+            CourseDirection::Up(3),
+            CourseDirection::Down(8),
+            CourseDirection::Forward(2),
+        ]
+    }
+
+    // Counts calls, to check every observer in a composition is notified
+    #[derive(Default)]
+    struct StepCounter {
+        before: usize,
+        after: usize
+    }
+
+    impl NavigationObserver for StepCounter {
+        fn before_step(&mut self, _index: usize, _op: &CourseDirection, _state: &NavigationState) {
+            self.before += 1;
+        }
+
+        fn after_step(&mut self, _index: usize, _op: &CourseDirection, _state: &NavigationState) {
+            self.after += 1;
+        }
+    }
+
+    #[test]
+    fn depth_threshold_alert_records_crossings_both_ways() {
+        // arrange
+        let mut alert = DepthThresholdAlert::new(4);
+        // act
+        navigate_observed(&example_course(), false, NavigationState::default(), &mut alert);
+        // assert
+        assert_eq!(alert.alerts, vec![
+            DepthAlert { index: 1, from_depth: 0, to_depth: 5 },
+            DepthAlert { index: 3, from_depth: 5, to_depth: 2 },
+            DepthAlert { index: 4, from_depth: 2, to_depth: 10 },
+        ]);
+        assert!(alert.alerts[0].descending());
+        assert!(!alert.alerts[1].descending());
+    }
+
+    #[test]
+    fn csv_log_writes_row_per_command() {
+        // arrange
+        let mut log = CsvLog::new(Vec::new());
+        let course = vec![CourseDirection::Down(2), CourseDirection::Forward(3)];
+        // act
+        navigate_observed(&course, true, NavigationState::default(), &mut log);
+        let result = String::from_utf8(log.finish().unwrap()).unwrap();
+        // assert
+        assert_eq!("index,command,depth,horizontal,aim\n0,down 2,0,0,2\n1,forward 3,6,3,2\n", result);
+    }
+
+    #[test]
+    fn observers_compose_in_pairs_and_lists() {
+        // arrange
+        let mut observers = (StepCounter::default(), vec![StepCounter::default(), StepCounter::default()]);
+        // act
+        let result = navigate_observed(&example_course(), true, NavigationState::default(), &mut observers);
+        // assert
+        assert_eq!(NavigationState { depth: 60, horizontal: 15, aim: 10 }, result);
+        assert_eq!(6, observers.0.before);
+        assert_eq!(6, observers.1[0].after);
+        assert_eq!(6, observers.1[1].before);
+    }
+}
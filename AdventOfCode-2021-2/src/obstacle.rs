@@ -0,0 +1,250 @@
+use crate::course::CourseDirection;
+use crate::file::is_blank_or_comment;
+use crate::navigation::{trajectory, NavigationState};
+use std::fmt;
+
+// A hazard covering a closed rectangle of the horizontal/depth plane. A single cell has equal minimum and maximum.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Obstacle {
+    pub horizontal_min: i32,
+    pub depth_min: i32,
+    pub horizontal_max: i32,
+    pub depth_max: i32
+}
+
+impl Obstacle {
+    pub fn rect(horizontal_a: i32, depth_a: i32, horizontal_b: i32, depth_b: i32) -> Self {
+        Self {
+            horizontal_min: horizontal_a.min(horizontal_b),
+            depth_min: depth_a.min(depth_b),
+            horizontal_max: horizontal_a.max(horizontal_b),
+            depth_max: depth_a.max(depth_b)
+        }
+    }
+
+    pub fn cell(horizontal: i32, depth: i32) -> Self {
+        Self::rect(horizontal, depth, horizontal, depth)
+    }
+
+    // Where the segment from one state to another first touches this obstacle, if it does at all
+    pub fn entry_point(&self, from: &NavigationState, to: &NavigationState) -> Option<(f64, f64)> {
+        // Liang-Barsky clipping of the segment against the rectangle
+        let (h0, d0) = (from.horizontal as f64, from.depth as f64);
+        let (dh, dd) = ((to.horizontal - from.horizontal) as f64, (to.depth - from.depth) as f64);
+        let edges = [
+            (-dh, h0 - self.horizontal_min as f64),
+            (dh, self.horizontal_max as f64 - h0),
+            (-dd, d0 - self.depth_min as f64),
+            (dd, self.depth_max as f64 - d0)
+        ];
+
+        let mut t_enter: f64 = 0.0;
+        let mut t_exit: f64 = 1.0;
+        for (p, q) in edges {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return None;
+                }
+            } else if p < 0.0 {
+                t_enter = t_enter.max(q / p);
+            } else {
+                t_exit = t_exit.min(q / p);
+            }
+        }
+
+        if t_enter > t_exit {
+            None
+        } else {
+            Some((h0 + t_enter * dh, d0 + t_enter * dd))
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Collision {
+    // Index of the course command whose segment hits the obstacle
+    pub command_index: usize,
+    // Index of the obstacle in its map
+    pub obstacle_index: usize,
+    pub horizontal: f64,
+    pub depth: f64
+}
+
+impl fmt::Display for Collision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Command {} hits obstacle {} at Depth: {}, Horizontal: {}", self.command_index + 1, self.obstacle_index + 1, self.depth, self.horizontal)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseObstacleError {
+    // When the line has the wrong number of tokens for its kind. First is line number, second is the full string
+    UnexpectedNumberOfTokens(usize, String),
+    // When the line doesn't start with a known obstacle kind (rect, cell). Second value is the invalid kind.
+    InvalidObstacleKind(usize, String),
+    // When a coordinate can't be parsed. Second value is the unparseable number.
+    UnparseableNumber(usize, String)
+}
+
+impl fmt::Display for ParseObstacleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseObstacleError::UnexpectedNumberOfTokens(line_num, line) => write!(f, "Line {}: wrong number of coordinates in \"{}\"", line_num + 1, line),
+            ParseObstacleError::InvalidObstacleKind(line_num, kind) => write!(f, "Line {}: unknown obstacle kind \"{}\"", line_num + 1, kind),
+            ParseObstacleError::UnparseableNumber(line_num, number) => write!(f, "Line {}: could not parse number \"{}\"", line_num + 1, number)
+        }
+    }
+}
+
+// Obstacle maps list one obstacle per line, as either "rect <horizontal> <depth> <horizontal> <depth>" giving
+// two opposite corners, or "cell <horizontal> <depth>". Blank lines and lines starting with # are ignored.
+pub fn parse_obstacle_map(input: &[String]) -> Result<Vec<Obstacle>, ParseObstacleError> {
+    input
+        .iter()
+        .enumerate()
+        .filter(|(_, line_str)| !is_blank_or_comment(line_str))
+        .map(|(line_num, line_str)| parse_obstacle_string(line_num, line_str))
+        .collect()
+}
+
+fn parse_obstacle_string(line_num: usize, input: &str) -> Result<Obstacle, ParseObstacleError> {
+    let split_string: Vec<&str> = input.split_whitespace().collect();
+    let expected_tokens = match split_string[0] {
+        "rect" => 5,
+        "cell" => 3,
+        _ => return Err(ParseObstacleError::InvalidObstacleKind(line_num, split_string[0].to_string()))
+    };
+    if split_string.len() != expected_tokens {
+        return Err(ParseObstacleError::UnexpectedNumberOfTokens(line_num, input.to_string()));
+    }
+
+    let coordinates = split_string[1..]
+        .iter()
+        .map(|s| s.parse::<i32>().map_err(|_| ParseObstacleError::UnparseableNumber(line_num, s.to_string())))
+        .collect::<Result<Vec<i32>, ParseObstacleError>>()?;
+
+    match coordinates[..] {
+        [h0, d0, h1, d1] => Ok(Obstacle::rect(h0, d0, h1, d1)),
+        [h, d] => Ok(Obstacle::cell(h, d)),
+        _ => unreachable!("Token count was checked above")
+    }
+}
+
+// Walks the whole path travelled by each command, reporting every obstacle it touches in course order
+pub fn find_collisions(operations: &[CourseDirection], with_aim: bool, obstacles: &[Obstacle]) -> Vec<Collision> {
+    trajectory(operations, with_aim, NavigationState::default())
+        .windows(2)
+        .enumerate()
+        .flat_map(|(command_index, segment)| {
+            obstacles
+                .iter()
+                .enumerate()
+                .filter_map(move |(obstacle_index, obstacle)| {
+                    obstacle.entry_point(&segment[0], &segment[1]).map(|(horizontal, depth)| Collision {
+                        command_index,
+                        obstacle_index,
+                        horizontal,
+                        depth
+                    })
+                })
+        })
+        .collect()
+}
+
+pub fn validate_safe_course(operations: &[CourseDirection], with_aim: bool, obstacles: &[Obstacle]) -> Result<(), Vec<Collision>> {
+    let collisions = find_collisions(operations, with_aim, obstacles);
+    if collisions.is_empty() {
+        Ok(())
+    } else {
+        Err(collisions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::course::CourseDirection;
+    use crate::navigation::NavigationState;
+    use super::find_collisions;
+    use super::parse_obstacle_map;
+    use super::validate_safe_course;
+    use super::Collision;
+    use super::Obstacle;
+    use super::ParseObstacleError;
+
+    fn state(depth: i32, horizontal: i32) -> NavigationState {
+        NavigationState { depth, horizontal, aim: 0 }
+    }
+
+    #[test]
+    fn parse_obstacle_map_parses_rects_and_cells() {
+        // arrange
+        let lines = vec!["# hazards".to_string(), "rect 5 10 2 4".to_string(), "".to_string(), "cell 7 3".to_string()];
+        // act
+        let result = parse_obstacle_map(&lines).unwrap();
+        // assert
+        assert_eq!(result, vec![Obstacle::rect(2, 4, 5, 10), Obstacle::cell(7, 3)]);
+        assert_eq!(2, result[0].horizontal_min);
+        assert_eq!(10, result[0].depth_max);
+    }
+
+    #[test]
+    fn parse_obstacle_map_reports_bad_lines() {
+        assert_eq!(
+            parse_obstacle_map(&["rect 1 2 3".to_string()]),
+            Err(ParseObstacleError::UnexpectedNumberOfTokens(0, "rect 1 2 3".to_string()))
+        );
+        assert_eq!(
+            parse_obstacle_map(&["cell 1 2".to_string(), "rock 1 2".to_string()]),
+            Err(ParseObstacleError::InvalidObstacleKind(1, "rock".to_string()))
+        );
+        assert_eq!(
+            parse_obstacle_map(&["cell 1 x".to_string()]),
+            Err(ParseObstacleError::UnparseableNumber(0, "x".to_string()))
+        );
+    }
+
+    #[test]
+    fn obstacle_entry_point_finds_first_contact() {
+        // arrange
+        let obstacle = Obstacle::rect(4, 0, 6, 10);
+        // act
+        let hit = obstacle.entry_point(&state(2, 0), &state(12, 10));
+        let miss = obstacle.entry_point(&state(2, 0), &state(2, 3));
+        // assert
+        assert_eq!(Some((4.0, 6.0)), hit);
+        assert_eq!(None, miss);
+    }
+
+    #[test]
+    fn find_collisions_checks_whole_segments_not_endpoints() {
+        // arrange
+        // Both ends of the forward segment are clear, but it passes straight through the cell
+        let course = vec![CourseDirection::Down(3), CourseDirection::Forward(10)];
+        let obstacles = vec![Obstacle::cell(5, 3)];
+        // act
+        let result = find_collisions(&course, false, &obstacles);
+        // assert
+        assert_eq!(result, vec![Collision { command_index: 1, obstacle_index: 0, horizontal: 5.0, depth: 3.0 }]);
+    }
+
+    #[test]
+    fn find_collisions_follows_sloped_aim_segments() {
+        // arrange
+        let course = vec![CourseDirection::Down(2), CourseDirection::Forward(4)];
+        let obstacles = vec![Obstacle::rect(3, 0, 3, 100), Obstacle::cell(1, 1)];
+        // act
+        let result = find_collisions(&course, true, &obstacles);
+        // assert
+        assert_eq!(result, vec![Collision { command_index: 1, obstacle_index: 0, horizontal: 3.0, depth: 6.0 }]);
+    }
+
+    #[test]
+    fn validate_safe_course_accepts_clear_course() {
+        // arrange
+        let course = vec![CourseDirection::Forward(5), CourseDirection::Down(5)];
+        let obstacles = vec![Obstacle::rect(0, 6, 10, 8)];
+        // act/assert
+        assert_eq!(Ok(()), validate_safe_course(&course, false, &obstacles));
+        assert!(validate_safe_course(&course, false, &[Obstacle::cell(5, 4)]).is_err());
+    }
+}
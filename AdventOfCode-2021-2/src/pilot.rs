@@ -0,0 +1,252 @@
+use crate::course::{parse_course, parse_course_string, write_course, CourseDirection, ParseCourseError};
+use crate::file::load_string_txt_to_vector;
+use crate::navigation::{navigation_model_name, parse_navigation_model, NavigationState};
+use std::fmt;
+use std::fs::File;
+use std::io::BufWriter;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PilotCommand {
+    Steer(CourseDirection),
+    Undo,
+    Reset,
+    Save(String),
+    Load(String),
+    Model(bool)
+}
+
+#[derive(Debug)]
+pub enum PilotError {
+    // When the line isn't a pilot command and doesn't parse as a course direction either
+    InvalidCourse(ParseCourseError),
+    // When save, load or model is given no argument. Value is the command name.
+    MissingArgument(String),
+    // When the model command names an unknown navigation model. Value is the unknown name.
+    UnknownModel(String),
+    // When undo is used before any direction has been applied
+    NothingToUndo,
+    // When reading or writing a course file fails
+    Io(std::io::Error)
+}
+
+impl fmt::Display for PilotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PilotError::InvalidCourse(e) => write!(f, "{}", e),
+            PilotError::MissingArgument(command) => write!(f, "The {} command needs an argument", command),
+            PilotError::UnknownModel(name) => write!(f, "Unknown navigation model \"{}\", expected simple or aim", name),
+            PilotError::NothingToUndo => write!(f, "Nothing to undo"),
+            PilotError::Io(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+// Steers the submarine one direction at a time, remembering every state along the way so moves can be undone
+pub struct Pilot {
+    with_aim: bool,
+    course: Vec<CourseDirection>,
+    // The state before any direction, followed by the state after each direction in the course
+    history: Vec<NavigationState>
+}
+
+impl Pilot {
+    pub fn new(with_aim: bool) -> Self {
+        Self {
+            with_aim,
+            course: Vec::new(),
+            history: vec![NavigationState::default()]
+        }
+    }
+
+    pub fn state(&self) -> NavigationState {
+        *self.history.last().expect("History always holds the start state")
+    }
+
+    pub fn course(&self) -> &[CourseDirection] {
+        &self.course
+    }
+
+    pub fn with_aim(&self) -> bool {
+        self.with_aim
+    }
+
+    pub fn execute(&mut self, line: &str) -> Result<(), PilotError> {
+        let command = parse_pilot_command(self.course.len(), line)?;
+        self.apply(command)
+    }
+
+    pub fn apply(&mut self, command: PilotCommand) -> Result<(), PilotError> {
+        match command {
+            PilotCommand::Steer(direction) => {
+                self.steer(direction);
+                Ok(())
+            },
+            PilotCommand::Undo => self.undo(),
+            PilotCommand::Reset => {
+                self.replace_course(Vec::new());
+                Ok(())
+            },
+            PilotCommand::Save(path) => {
+                let file = File::create(path).map_err(PilotError::Io)?;
+                write_course(&mut BufWriter::new(file), &self.course).map_err(PilotError::Io)
+            },
+            PilotCommand::Load(path) => {
+                let lines = load_string_txt_to_vector(&path).map_err(PilotError::Io)?;
+                let course = parse_course(&lines).map_err(PilotError::InvalidCourse)?;
+                self.replace_course(course);
+                Ok(())
+            },
+            PilotCommand::Model(with_aim) => {
+                self.with_aim = with_aim;
+                let course = std::mem::take(&mut self.course);
+                self.replace_course(course);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn steer(&mut self, direction: CourseDirection) {
+        let mut state = self.state();
+        state.apply(&direction, self.with_aim);
+        self.course.push(direction);
+        self.history.push(state);
+    }
+
+    pub fn undo(&mut self) -> Result<(), PilotError> {
+        self.course.pop().ok_or(PilotError::NothingToUndo)?;
+        self.history.pop();
+        Ok(())
+    }
+
+    // Replays a whole course from the start, which is also how a change of model is applied
+    fn replace_course(&mut self, course: Vec<CourseDirection>) {
+        self.course.clear();
+        self.history.truncate(1);
+        for direction in course {
+            self.steer(direction);
+        }
+    }
+}
+
+impl fmt::Display for Pilot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{} model, step {}] {}", navigation_model_name(self.with_aim), self.course.len(), self.state())
+    }
+}
+
+pub fn parse_pilot_command(line_num: usize, input: &str) -> Result<PilotCommand, PilotError> {
+    let (command, argument) = match input.trim().split_once(char::is_whitespace) {
+        Some((command, argument)) => (command, Some(argument.trim())),
+        None => (input.trim(), None)
+    };
+
+    match (command, argument) {
+        ("undo", None) => Ok(PilotCommand::Undo),
+        ("reset", None) => Ok(PilotCommand::Reset),
+        ("save", Some(path)) => Ok(PilotCommand::Save(path.to_string())),
+        ("load", Some(path)) => Ok(PilotCommand::Load(path.to_string())),
+        ("model", Some(name)) => parse_navigation_model(name)
+            .map(PilotCommand::Model)
+            .ok_or(PilotError::UnknownModel(name.to_string())),
+        ("save", None) | ("load", None) | ("model", None) => Err(PilotError::MissingArgument(command.to_string())),
+        _ => parse_course_string(line_num, input)
+            .map(PilotCommand::Steer)
+            .map_err(PilotError::InvalidCourse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::course::{CourseDirection, ParseCourseError};
+    use crate::navigation::NavigationState;
+    use super::parse_pilot_command;
+    use super::Pilot;
+    use super::PilotCommand;
+    use super::PilotError;
+
+    #[test]
+    fn parse_pilot_command_parses_commands_and_directions() {
+        assert_eq!(PilotCommand::Steer(CourseDirection::Down(4)), parse_pilot_command(0, "down 4").unwrap());
+        assert_eq!(PilotCommand::Undo, parse_pilot_command(0, "undo").unwrap());
+        assert_eq!(PilotCommand::Reset, parse_pilot_command(0, " reset ").unwrap());
+        assert_eq!(PilotCommand::Save("out.txt".to_string()), parse_pilot_command(0, "save out.txt").unwrap());
+        assert_eq!(PilotCommand::Load("in.txt".to_string()), parse_pilot_command(0, "load in.txt").unwrap());
+        assert_eq!(PilotCommand::Model(true), parse_pilot_command(0, "model aim").unwrap());
+    }
+
+    #[test]
+    fn parse_pilot_command_rejects_bad_input() {
+        match parse_pilot_command(3, "sideways 2") {
+            Err(PilotError::InvalidCourse(e)) => assert_eq!(ParseCourseError::InvalidCourseString(3, "sideways".to_string()), e),
+            _ => panic!("Expected failure")
+        }
+        match parse_pilot_command(0, "model wobbly") {
+            Err(PilotError::UnknownModel(name)) => assert_eq!("wobbly", name),
+            _ => panic!("Expected failure")
+        }
+        match parse_pilot_command(0, "save") {
+            Err(PilotError::MissingArgument(command)) => assert_eq!("save", command),
+            _ => panic!("Expected failure")
+        }
+    }
+
+    #[test]
+    fn pilot_applies_each_step_immediately() {
+        // arrange
+        let mut pilot = Pilot::new(true);
+        // act
+        pilot.execute("forward 5").unwrap();
+        pilot.execute("down 5").unwrap();
+        pilot.execute("forward 8").unwrap();
+        // assert
+        assert_eq!(NavigationState { depth: 40, horizontal: 13, aim: 5 }, pilot.state());
+        assert_eq!(3, pilot.course().len());
+    }
+
+    #[test]
+    fn pilot_undo_and_reset_restore_earlier_states() {
+        // arrange
+        let mut pilot = Pilot::new(true);
+        pilot.execute("down 2").unwrap();
+        pilot.execute("forward 3").unwrap();
+        // act
+        pilot.execute("undo").unwrap();
+        let after_undo = pilot.state();
+        pilot.execute("reset").unwrap();
+        // assert
+        assert_eq!(NavigationState { depth: 0, horizontal: 0, aim: 2 }, after_undo);
+        assert_eq!(NavigationState::default(), pilot.state());
+        assert!(matches!(pilot.execute("undo"), Err(PilotError::NothingToUndo)));
+    }
+
+    #[test]
+    fn pilot_model_change_replays_course() {
+        // arrange
+        let mut pilot = Pilot::new(false);
+        pilot.execute("down 2").unwrap();
+        pilot.execute("forward 3").unwrap();
+        // act
+        pilot.execute("model aim").unwrap();
+        // assert
+        assert!(pilot.with_aim());
+        assert_eq!(NavigationState { depth: 6, horizontal: 3, aim: 2 }, pilot.state());
+    }
+
+    #[test]
+    fn pilot_save_then_load_restores_course() {
+        // arrange
+        let path = std::env::temp_dir().join("pilot_save_then_load_restores_course.txt");
+        let path = path.to_str().unwrap();
+        let mut pilot = Pilot::new(false);
+        pilot.execute("forward 7").unwrap();
+        pilot.execute("up 1").unwrap();
+        // act
+        pilot.execute(&format!("save {}", path)).unwrap();
+        let mut loaded = Pilot::new(false);
+        loaded.execute(&format!("load {}", path)).unwrap();
+        // assert
+        assert_eq!(pilot.course(), loaded.course());
+        assert_eq!(pilot.state(), loaded.state());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,501 @@
+use crate::course::CourseDirection;
+use crate::course::invert_direction;
+use crate::navigation::{navigate_from, NavigationState, SubCoordinates};
+use std::fmt;
+
+// How many first leg lengths plan_keeping_start_aim tries before giving up
+const MAX_FIRST_LEGS: i32 = 100_000;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum PlanObjective {
+    // Generate the shortest possible list of commands
+    #[default]
+    FewestCommands,
+    // Minimise the sum of every command's magnitude
+    SmallestTotalMovement
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct PlanOptions {
+    pub start: NavigationState,
+    pub with_aim: bool,
+    pub objective: PlanObjective,
+    // The deepest the submarine may go at any point on the course
+    pub max_depth: Option<i32>,
+    // Whether the course may rise above the surface (depth 0), which is otherwise the shallowest it may go
+    pub allow_negative_depth: bool,
+    // Whether the course may use negative forward commands to move backwards
+    pub allow_reverse: bool
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ReturnMode {
+    // Undo every outbound command in reverse order, restoring the aim as well as the position
+    Retrace,
+    // Head straight back to the start with the smallest total movement
+    Shortest
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PlanError {
+    // When the target is behind the start and reversing isn't allowed. First is the start horizontal, second is the target horizontal.
+    HorizontalUnreachable(i32, i32),
+    // When the start or target depth lies outside the depth constraints. Value is the offending depth.
+    DepthOutOfBounds(i32),
+    // When navigating with aim, depth only changes while moving forward. Value is the depth change that can't be made.
+    DepthUnreachableWithoutForward(i32),
+    // When a distance, depth or aim the plan needs is too large to fit in an i32
+    OutOfRange
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanError::HorizontalUnreachable(start, target) => write!(f, "Target horizontal {} is behind the start at {}", target, start),
+            PlanError::DepthOutOfBounds(depth) => write!(f, "Depth {} is outside the allowed depths", depth),
+            PlanError::DepthUnreachableWithoutForward(change) => write!(f, "Depth change of {} needs forward movement when navigating with aim", change),
+            PlanError::OutOfRange => write!(f, "Target is too far from the start to plan a course")
+        }
+    }
+}
+
+pub fn plan_course(target: &SubCoordinates, options: &PlanOptions) -> Result<Vec<CourseDirection>, PlanError> {
+    let start = options.start;
+    check_depth(start.depth, options)?;
+    check_depth(target.depth(), options)?;
+
+    let horizontal_change = target.horizontal().checked_sub(start.horizontal).ok_or(PlanError::OutOfRange)?;
+    let depth_change = target.depth().checked_sub(start.depth).ok_or(PlanError::OutOfRange)?;
+    if horizontal_change < 0 && !options.allow_reverse {
+        return Err(PlanError::HorizontalUnreachable(start.horizontal, target.horizontal()));
+    }
+
+    if !options.with_aim {
+        plan_without_aim(horizontal_change, depth_change)
+    } else if horizontal_change < 0 {
+        // Moving backwards at aim a changes depth just as moving forwards at aim -a does, so plan the mirror
+        // image and flip it back. Every depth along the way is unchanged, so the constraints still hold.
+        let distance = horizontal_change.checked_neg().ok_or(PlanError::OutOfRange)?;
+        let mirrored_aim = start.aim.checked_neg().ok_or(PlanError::OutOfRange)?;
+        let mirrored = plan_with_aim(distance, depth_change, mirrored_aim, options)?;
+        mirrored.iter().map(|op| invert_direction(op).ok_or(PlanError::OutOfRange)).collect()
+    } else {
+        plan_with_aim(horizontal_change, depth_change, start.aim, options)
+    }
+}
+
+pub fn return_course(outbound: &[CourseDirection], start: NavigationState, with_aim: bool, mode: ReturnMode) -> Result<Vec<CourseDirection>, PlanError> {
+    match mode {
+        ReturnMode::Retrace => outbound.iter().rev().map(|op| invert_direction(op).ok_or(PlanError::OutOfRange)).collect(),
+        ReturnMode::Shortest => plan_return(navigate_from(outbound, with_aim, start), start.coordinates(), with_aim)
+    }
+}
+
+pub fn plan_return(current: NavigationState, home: SubCoordinates, with_aim: bool) -> Result<Vec<CourseDirection>, PlanError> {
+    let options = PlanOptions {
+        start: current,
+        with_aim,
+        objective: PlanObjective::SmallestTotalMovement,
+        max_depth: None,
+        allow_negative_depth: false,
+        allow_reverse: true
+    };
+    plan_course(&home, &options)
+}
+
+fn plan_without_aim(horizontal_change: i32, depth_change: i32) -> Result<Vec<CourseDirection>, PlanError> {
+    // Both objectives agree here: one vertical and one horizontal move is both fewest and smallest
+    let mut course = Vec::new();
+    course.extend(aim_change(0, depth_change)?);
+    if horizontal_change != 0 {
+        course.push(CourseDirection::Forward(horizontal_change));
+    }
+    Ok(course)
+}
+
+fn plan_with_aim(horizontal_change: i32, depth_change: i32, start_aim: i32, options: &PlanOptions) -> Result<Vec<CourseDirection>, PlanError> {
+    if horizontal_change == 0 {
+        return if depth_change == 0 {
+            Ok(Vec::new())
+        } else if options.allow_reverse {
+            plan_out_and_back(depth_change, start_aim, options)
+        } else {
+            Err(PlanError::DepthUnreachableWithoutForward(depth_change))
+        };
+    }
+
+    if depth_change % horizontal_change == 0 {
+        // A single aim covers the whole distance, which is optimal for both objectives
+        let mut course: Vec<CourseDirection> = aim_change(start_aim, depth_change / horizontal_change)?.into_iter().collect();
+        course.push(CourseDirection::Forward(horizontal_change));
+        return Ok(course);
+    }
+
+    if options.objective == PlanObjective::FewestCommands {
+        if let Some(course) = plan_keeping_start_aim(horizontal_change, depth_change, start_aim, options) {
+            return Ok(course);
+        }
+    }
+
+    plan_with_adjacent_aims(horizontal_change, depth_change, start_aim)
+}
+
+// Tries to spend the first part of the course at the starting aim, then a single aim change covers the rest.
+// This needs only three commands, but the first leg may carry the submarine outside the depth constraints, or
+// beyond what an i32 can hold. Only the first MAX_FIRST_LEGS lengths are tried, since the adjacent aims plan
+// always works at the cost of one more command.
+fn plan_keeping_start_aim(horizontal_change: i32, depth_change: i32, start_aim: i32, options: &PlanOptions) -> Option<Vec<CourseDirection>> {
+    (1..horizontal_change.min(MAX_FIRST_LEGS + 1)).find_map(|first_leg| {
+        let second_leg = horizontal_change - first_leg;
+        let first_leg_depth = start_aim.checked_mul(first_leg)?;
+        let remaining_depth = depth_change.checked_sub(first_leg_depth)?;
+        let midpoint_depth = options.start.depth.checked_add(first_leg_depth)?;
+        if remaining_depth % second_leg != 0 || check_depth(midpoint_depth, options).is_err() {
+            return None;
+        }
+
+        let mut course = vec![CourseDirection::Forward(first_leg)];
+        course.extend(aim_change(start_aim, remaining_depth / second_leg).ok()?);
+        course.push(CourseDirection::Forward(second_leg));
+        Some(course)
+    })
+}
+
+// Splits the course between the two integer aims either side of the exact (fractional) aim needed.
+// Visiting them in order of distance from the starting aim keeps the total aim change minimal, and
+// as both legs descend (or ascend) together the depth never leaves the range between start and target.
+fn plan_with_adjacent_aims(horizontal_change: i32, depth_change: i32, start_aim: i32) -> Result<Vec<CourseDirection>, PlanError> {
+    let low_aim = depth_change.div_euclid(horizontal_change);
+    let high_aim_distance = depth_change.rem_euclid(horizontal_change);
+    let low_aim_distance = horizontal_change - high_aim_distance;
+
+    let mut course = Vec::new();
+    if start_aim <= low_aim {
+        course.extend(aim_change(start_aim, low_aim)?);
+        course.push(CourseDirection::Forward(low_aim_distance));
+        course.push(CourseDirection::Down(1));
+        course.push(CourseDirection::Forward(high_aim_distance));
+    } else {
+        course.extend(aim_change(start_aim, low_aim + 1)?);
+        course.push(CourseDirection::Forward(high_aim_distance));
+        course.push(CourseDirection::Up(1));
+        course.push(CourseDirection::Forward(low_aim_distance));
+    }
+    Ok(course)
+}
+
+// Changes depth on the spot by moving forward one unit and straight back again at a different aim.
+// The outbound leg keeps the starting aim unless that would leave the depth constraints.
+fn plan_out_and_back(depth_change: i32, start_aim: i32, options: &PlanOptions) -> Result<Vec<CourseDirection>, PlanError> {
+    let out_depth = options.start.depth.checked_add(start_aim);
+    let out_aim = if out_depth.is_some_and(|depth| check_depth(depth, options).is_ok()) { start_aim } else { 0 };
+    let back_aim = out_aim.checked_sub(depth_change).ok_or(PlanError::OutOfRange)?;
+
+    let mut course: Vec<CourseDirection> = aim_change(start_aim, out_aim)?.into_iter().collect();
+    course.push(CourseDirection::Forward(1));
+    course.extend(aim_change(out_aim, back_aim)?);
+    course.push(CourseDirection::Forward(-1));
+    Ok(course)
+}
+
+// The command that moves a value (aim, or depth when navigating without aim) from one level to another
+fn aim_change(from: i32, to: i32) -> Result<Option<CourseDirection>, PlanError> {
+    if to > from {
+        Ok(Some(CourseDirection::Down(to.checked_sub(from).ok_or(PlanError::OutOfRange)?)))
+    } else if to < from {
+        Ok(Some(CourseDirection::Up(from.checked_sub(to).ok_or(PlanError::OutOfRange)?)))
+    } else {
+        Ok(None)
+    }
+}
+
+fn check_depth(depth: i32, options: &PlanOptions) -> Result<(), PlanError> {
+    let too_deep = options.max_depth.is_some_and(|max| depth > max);
+    if (depth < 0 && !options.allow_negative_depth) || too_deep {
+        Err(PlanError::DepthOutOfBounds(depth))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::course::CourseDirection;
+    use crate::navigation::{navigate, navigate_from, NavigationState, SubCoordinates};
+    use super::plan_course;
+    use super::plan_return;
+    use super::return_course;
+    use super::PlanError;
+    use super::PlanObjective;
+    use super::PlanOptions;
+    use super::ReturnMode;
+
+    fn example_course() -> Vec<CourseDirection> {
+        vec![
+            CourseDirection::Forward(5),
+            CourseDirection::Down(5),
+            CourseDirection::Forward(8),
+            CourseDirection::Up(3),
+            CourseDirection::Down(8),
+            CourseDirection::Forward(2),
+        ]
+    }
+
+    fn total_movement(course: &[CourseDirection]) -> i32 {
+        course
+            .iter()
+            .map(|c| match c {
+                CourseDirection::Forward(n) | CourseDirection::Up(n) | CourseDirection::Down(n) => n.abs()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn plan_course_without_aim_reaches_target() {
+        // arrange
+        let target = SubCoordinates::new(10, 15);
+        let options = PlanOptions::default();
+        // act
+        let result = plan_course(&target, &options).unwrap();
+        // assert
+        assert_eq!(result, vec![CourseDirection::Down(10), CourseDirection::Forward(15)]);
+        assert_eq!(navigate_from(&result, false, options.start).coordinates(), target);
+    }
+
+    #[test]
+    fn plan_course_with_aim_uses_single_aim_when_divisible() {
+        // arrange
+        let target = SubCoordinates::new(60, 15);
+        let options = PlanOptions { with_aim: true, ..Default::default() };
+        // act
+        let result = plan_course(&target, &options).unwrap();
+        // assert
+        assert_eq!(result, vec![CourseDirection::Down(4), CourseDirection::Forward(15)]);
+    }
+
+    #[test]
+    fn plan_course_with_aim_fewest_commands_keeps_start_aim() {
+        // arrange
+        let target = SubCoordinates::new(5, 2);
+        let options = PlanOptions { with_aim: true, ..Default::default() };
+        // act
+        let result = plan_course(&target, &options).unwrap();
+        // assert
+        assert_eq!(result, vec![CourseDirection::Forward(1), CourseDirection::Down(5), CourseDirection::Forward(1)]);
+        assert_eq!(navigate_from(&result, true, options.start).coordinates(), target);
+    }
+
+    #[test]
+    fn plan_course_with_aim_smallest_movement_splits_between_aims() {
+        // arrange
+        let target = SubCoordinates::new(5, 2);
+        let options = PlanOptions {
+            with_aim: true,
+            objective: PlanObjective::SmallestTotalMovement,
+            ..Default::default()
+        };
+        // act
+        let result = plan_course(&target, &options).unwrap();
+        // assert
+        assert_eq!(result, vec![
+            CourseDirection::Down(2),
+            CourseDirection::Forward(1),
+            CourseDirection::Down(1),
+            CourseDirection::Forward(1)
+        ]);
+        assert_eq!(5, total_movement(&result));
+        assert_eq!(navigate_from(&result, true, options.start).coordinates(), target);
+    }
+
+    #[test]
+    fn plan_course_with_aim_reaches_target_from_start_state() {
+        // arrange
+        let target = SubCoordinates::new(3, 17);
+        let start = NavigationState { depth: 20, horizontal: 4, aim: 6 };
+        let options = PlanOptions { start, with_aim: true, ..Default::default() };
+        // act
+        let result = plan_course(&target, &options).unwrap();
+        // assert
+        assert_eq!(navigate_from(&result, true, start).coordinates(), target);
+    }
+
+    #[test]
+    fn plan_course_with_aim_avoids_leaving_the_surface() {
+        // arrange
+        let target = SubCoordinates::new(5, 2);
+        let options = PlanOptions {
+            start: NavigationState { depth: 0, horizontal: 0, aim: -1 },
+            with_aim: true,
+            ..Default::default()
+        };
+        // act
+        let result = plan_course(&target, &options).unwrap();
+        // assert
+        // Keeping the start aim for the first leg would surface at depth -1
+        assert_eq!(result, vec![
+            CourseDirection::Down(3),
+            CourseDirection::Forward(1),
+            CourseDirection::Down(1),
+            CourseDirection::Forward(1)
+        ]);
+    }
+
+    #[test]
+    fn plan_course_target_behind_start_is_unreachable() {
+        // arrange
+        let target = SubCoordinates::new(0, 3);
+        let options = PlanOptions {
+            start: NavigationState { depth: 0, horizontal: 5, aim: 0 },
+            ..Default::default()
+        };
+        // act
+        let result = plan_course(&target, &options);
+        // assert
+        assert_eq!(result, Err(PlanError::HorizontalUnreachable(5, 3)));
+    }
+
+    #[test]
+    fn plan_course_target_beyond_max_depth_is_unreachable() {
+        // arrange
+        let target = SubCoordinates::new(50, 3);
+        let options = PlanOptions { max_depth: Some(40), ..Default::default() };
+        // act
+        let result = plan_course(&target, &options);
+        // assert
+        assert_eq!(result, Err(PlanError::DepthOutOfBounds(50)));
+    }
+
+    #[test]
+    fn plan_course_with_aim_depth_change_without_forward_is_unreachable() {
+        // arrange
+        let target = SubCoordinates::new(4, 0);
+        let options = PlanOptions { with_aim: true, ..Default::default() };
+        // act
+        let result = plan_course(&target, &options);
+        // assert
+        assert_eq!(result, Err(PlanError::DepthUnreachableWithoutForward(4)));
+    }
+
+    #[test]
+    fn plan_course_with_reverse_allowed_moves_backwards() {
+        // arrange
+        let target = SubCoordinates::new(2, 1);
+        let start = NavigationState { depth: 9, horizontal: 4, aim: 1 };
+        let options = PlanOptions { start, with_aim: true, allow_reverse: true, ..Default::default() };
+        // act
+        let result = plan_course(&target, &options).unwrap();
+        // assert
+        assert_eq!(navigate_from(&result, true, start).coordinates(), target);
+    }
+
+    #[test]
+    fn plan_course_with_reverse_allowed_changes_depth_on_the_spot() {
+        // arrange
+        let target = SubCoordinates::new(7, 4);
+        let start = NavigationState { depth: 3, horizontal: 4, aim: 2 };
+        let options = PlanOptions { start, with_aim: true, allow_reverse: true, ..Default::default() };
+        // act
+        let result = plan_course(&target, &options).unwrap();
+        // assert
+        assert_eq!(result, vec![CourseDirection::Forward(1), CourseDirection::Up(4), CourseDirection::Forward(-1)]);
+        assert_eq!(navigate_from(&result, true, start).coordinates(), target);
+    }
+
+    #[test]
+    fn return_course_retrace_restores_origin_and_aim() {
+        for with_aim in [false, true] {
+            // arrange
+            let outbound = example_course();
+            // act
+            let homeward = return_course(&outbound, NavigationState::default(), with_aim, ReturnMode::Retrace).unwrap();
+            let full_course: Vec<CourseDirection> = outbound.iter().chain(homeward.iter()).copied().collect();
+            // assert
+            assert_eq!(homeward.len(), outbound.len());
+            assert_eq!(navigate_from(&full_course, with_aim, NavigationState::default()), NavigationState::default());
+        }
+    }
+
+    #[test]
+    fn return_course_retrace_rejects_forward_that_cannot_be_undone() {
+        // arrange
+        let outbound = vec![CourseDirection::Forward(i32::MIN)];
+        // act
+        let result = return_course(&outbound, NavigationState::default(), false, ReturnMode::Retrace);
+        // assert
+        assert_eq!(result, Err(PlanError::OutOfRange));
+    }
+
+    #[test]
+    fn return_course_shortest_returns_to_origin() {
+        for with_aim in [false, true] {
+            // arrange
+            let outbound = example_course();
+            // act
+            let homeward = return_course(&outbound, NavigationState::default(), with_aim, ReturnMode::Shortest).unwrap();
+            let full_course: Vec<CourseDirection> = outbound.iter().chain(homeward.iter()).copied().collect();
+            // assert
+            assert!(homeward.len() <= 2);
+            assert_eq!(navigate(&full_course, with_aim), SubCoordinates::default());
+        }
+    }
+
+    #[test]
+    fn plan_return_with_aim_undoes_final_aim() {
+        // arrange
+        let current = NavigationState { depth: 60, horizontal: 15, aim: 10 };
+        // act
+        let result = plan_return(current, SubCoordinates::default(), true).unwrap();
+        // assert
+        assert_eq!(result, vec![CourseDirection::Up(6), CourseDirection::Forward(-15)]);
+    }
+
+    #[test]
+    fn plan_return_from_above_surface_fails() {
+        // arrange
+        let current = NavigationState { depth: -3, horizontal: 2, aim: 0 };
+        // act
+        let result = plan_return(current, SubCoordinates::default(), false);
+        // assert
+        assert_eq!(result, Err(PlanError::DepthOutOfBounds(-3)));
+    }
+
+    #[test]
+    fn plan_course_with_large_start_aim_reaches_target() {
+        // arrange
+        let target = SubCoordinates::new(7, 1_000_000);
+        let start = NavigationState { depth: 0, horizontal: 0, aim: 1_000_000 };
+        let options = PlanOptions { start, with_aim: true, ..Default::default() };
+        // act
+        let result = plan_course(&target, &options).unwrap();
+        // assert
+        assert_eq!(navigate_from(&result, true, start).coordinates(), target);
+    }
+
+    #[test]
+    fn plan_course_rejects_changes_too_large_for_i32() {
+        // arrange
+        let target = SubCoordinates::new(0, i32::MIN);
+        let start = NavigationState { depth: 0, horizontal: 1, aim: 0 };
+        let options = PlanOptions { start, allow_reverse: true, ..Default::default() };
+        // act
+        let result = plan_course(&target, &options);
+        // assert
+        assert_eq!(result, Err(PlanError::OutOfRange));
+    }
+
+    #[test]
+    fn plan_course_can_start_above_surface_when_allowed() {
+        // arrange
+        let target = SubCoordinates::new(5, 10);
+        let start = NavigationState { depth: -20, horizontal: 0, aim: 0 };
+        let options = PlanOptions { start, with_aim: true, allow_negative_depth: true, ..Default::default() };
+        // act
+        let result = plan_course(&target, &options).unwrap();
+        // assert
+        assert_eq!(navigate_from(&result, true, start).coordinates(), target);
+        assert_eq!(
+            plan_course(&target, &PlanOptions { allow_negative_depth: false, ..options }),
+            Err(PlanError::DepthOutOfBounds(-20))
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use crate::course::CourseDirection;
+use crate::navigation::{trajectory, NavigationState};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DepthAlignment {
+    // Reading n was taken after the nth command, with reading 0 at the start
+    StepIndex,
+    // Reading n was taken at horizontal position n
+    Horizontal
+}
+
+pub fn parse_depth_alignment(name: &str) -> Option<DepthAlignment> {
+    match name {
+        "step" => Some(DepthAlignment::StepIndex),
+        "horizontal" => Some(DepthAlignment::Horizontal),
+        _ => None
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DepthComparison {
+    // Step index or horizontal position, depending on the alignment
+    pub position: usize,
+    pub computed: f64,
+    pub measured: f64
+}
+
+impl DepthComparison {
+    pub fn difference(&self) -> f64 {
+        self.measured - self.computed
+    }
+}
+
+// The two simple corrections that could explain the measured depths, each with how well it fits
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DepthFit {
+    // Measured is best approximated by computed + offset
+    pub offset: f64,
+    pub offset_rms_error: f64,
+    // Measured is best approximated by computed * scale
+    pub scale: f64,
+    pub scale_rms_error: f64
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Reconciliation {
+    pub comparisons: Vec<DepthComparison>,
+    pub tolerance: f64,
+    pub fit: DepthFit
+}
+
+impl Reconciliation {
+    // Every position where computed and measured depth differ by more than the tolerance
+    pub fn disagreements(&self) -> Vec<DepthComparison> {
+        self.comparisons
+            .iter()
+            .filter(|c| c.difference().abs() > self.tolerance)
+            .copied()
+            .collect()
+    }
+}
+
+// Lines up the depths a course passes through with a series of sonar readings. Readings beyond the end of the
+// course, or at horizontal positions it never reaches, are left out.
+pub fn reconcile_depths(operations: &[CourseDirection], with_aim: bool, measured: &[i32], alignment: DepthAlignment, tolerance: f64) -> Reconciliation {
+    let states = trajectory(operations, with_aim, NavigationState::default());
+    let computed = match alignment {
+        DepthAlignment::StepIndex => states.iter().map(|s| Some(s.depth as f64)).collect(),
+        DepthAlignment::Horizontal => depth_by_horizontal(&states, measured.len())
+    };
+
+    let comparisons: Vec<DepthComparison> = computed
+        .iter()
+        .zip(measured.iter())
+        .enumerate()
+        .filter_map(|(position, (computed, &measured))| computed.map(|computed| DepthComparison { position, computed, measured: measured as f64 }))
+        .collect();
+    let fit = fit_depths(&comparisons);
+
+    Reconciliation { comparisons, tolerance, fit }
+}
+
+// The depth at each whole horizontal position that has a reading, from 0 up to readings - 1. Forward moves are
+// interpolated along their length, and where the course passes a position more than once the last visit wins.
+// Only positions with a reading are visited, so a long forward move costs no more than a short one.
+fn depth_by_horizontal(states: &[NavigationState], readings: usize) -> Vec<Option<f64>> {
+    let mut depths = vec![None; readings];
+    let in_readings = |h: i32| h >= 0 && (h as usize) < readings;
+    if let Some(start) = states.first().filter(|s| in_readings(s.horizontal)) {
+        depths[start.horizontal as usize] = Some(start.depth as f64);
+    }
+
+    for segment in states.windows(2) {
+        let (from, to) = (&segment[0], &segment[1]);
+        // Each position is set once per segment, so the order they're visited in doesn't matter
+        let low = from.horizontal.min(to.horizontal).max(0) as usize;
+        let high = (from.horizontal.max(to.horizontal) as i64).min(readings as i64 - 1);
+        if high < low as i64 {
+            continue;
+        }
+        for (h, depth) in depths.iter_mut().enumerate().take(high as usize + 1).skip(low) {
+            let h = h as i32;
+            *depth = Some(if to.horizontal == from.horizontal {
+                to.depth as f64
+            } else {
+                let progress = (h as f64 - from.horizontal as f64) / (to.horizontal as f64 - from.horizontal as f64);
+                from.depth as f64 + (to.depth as f64 - from.depth as f64) * progress
+            });
+        }
+    }
+
+    depths
+}
+
+// Least squares estimates of a constant offset, and of a scale factor through the surface
+fn fit_depths(comparisons: &[DepthComparison]) -> DepthFit {
+    if comparisons.is_empty() {
+        return DepthFit { offset: 0.0, offset_rms_error: 0.0, scale: 1.0, scale_rms_error: 0.0 };
+    }
+
+    let count = comparisons.len() as f64;
+    let offset = comparisons.iter().map(|c| c.difference()).sum::<f64>() / count;
+    let computed_squares: f64 = comparisons.iter().map(|c| c.computed * c.computed).sum();
+    let scale = if computed_squares == 0.0 {
+        1.0
+    } else {
+        comparisons.iter().map(|c| c.computed * c.measured).sum::<f64>() / computed_squares
+    };
+
+    let rms_error = |predict: &dyn Fn(f64) -> f64| {
+        (comparisons.iter().map(|c| (c.measured - predict(c.computed)).powi(2)).sum::<f64>() / count).sqrt()
+    };
+
+    DepthFit {
+        offset,
+        offset_rms_error: rms_error(&|computed| computed + offset),
+        scale,
+        scale_rms_error: rms_error(&|computed| computed * scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::course::CourseDirection;
+    use super::reconcile_depths;
+    use super::DepthAlignment;
+    use super::DepthComparison;
+
+    fn example_course() -> Vec<CourseDirection> {
+        vec![
+            CourseDirection::Forward(5),
+            CourseDirection::Down(5),
+            CourseDirection::Forward(8),
+            CourseDirection::Up(3),
+            CourseDirection::Down(8),
+            CourseDirection::Forward(2),
+        ]
+    }
+
+    #[test]
+    fn reconcile_depths_by_step_reports_disagreements() {
+        // arrange
+        let measured = vec![0, 0, 5, 5, 9, 10, 10];
+        // act
+        let result = reconcile_depths(&example_course(), false, &measured, DepthAlignment::StepIndex, 0.5);
+        // assert
+        assert_eq!(7, result.comparisons.len());
+        assert_eq!(result.disagreements(), vec![DepthComparison { position: 4, computed: 2.0, measured: 9.0 }]);
+    }
+
+    #[test]
+    fn reconcile_depths_estimates_constant_offset() {
+        // arrange
+        let measured = vec![3, 3, 8, 8, 5, 13, 13];
+        // act
+        let result = reconcile_depths(&example_course(), false, &measured, DepthAlignment::StepIndex, 0.0);
+        // assert
+        assert!((result.fit.offset - 3.0).abs() < 1e-9);
+        assert!(result.fit.offset_rms_error < 1e-9);
+        assert!(result.fit.scale_rms_error > 1.0);
+    }
+
+    #[test]
+    fn reconcile_depths_estimates_scale_factor() {
+        // arrange
+        let measured = vec![0, 0, 10, 10, 4, 20, 20];
+        // act
+        let result = reconcile_depths(&example_course(), false, &measured, DepthAlignment::StepIndex, 0.0);
+        // assert
+        assert!((result.fit.scale - 2.0).abs() < 1e-9);
+        assert!(result.fit.scale_rms_error < 1e-9);
+    }
+
+    #[test]
+    fn reconcile_depths_by_horizontal_interpolates_forward_moves() {
+        // arrange
+        let course = vec![CourseDirection::Down(2), CourseDirection::Forward(3)];
+        let measured = vec![0, 2, 4, 6, 8];
+        // act
+        let result = reconcile_depths(&course, true, &measured, DepthAlignment::Horizontal, 0.0);
+        // assert
+        // The course ends at horizontal 3, so the fifth reading is left out
+        assert_eq!(4, result.comparisons.len());
+        assert_eq!(result.comparisons[2], DepthComparison { position: 2, computed: 4.0, measured: 4.0 });
+        assert!(result.disagreements().is_empty());
+    }
+
+    #[test]
+    fn reconcile_depths_by_horizontal_only_visits_positions_with_readings() {
+        // arrange
+        let course = vec![CourseDirection::Down(4), CourseDirection::Forward(2_000_000_000)];
+        let measured = vec![4, 4, 4];
+        // act
+        let result = reconcile_depths(&course, false, &measured, DepthAlignment::Horizontal, 0.0);
+        // assert
+        assert_eq!(3, result.comparisons.len());
+        assert!(result.disagreements().is_empty());
+    }
+}
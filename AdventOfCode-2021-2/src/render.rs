@@ -0,0 +1,185 @@
+use crate::course::CourseDirection;
+use crate::navigation::{navigation_model_name, trajectory, NavigationState};
+use std::fmt::Write;
+
+const SVG_MARGIN: f64 = 20.0;
+
+// Fits a set of states into a drawing area, with horizontal along x and depth increasing down y.
+// Each axis is scaled separately, as depths under the aim model dwarf horizontal distances.
+struct Scale {
+    min_horizontal: i32,
+    min_depth: i32,
+    horizontal_span: f64,
+    depth_span: f64
+}
+
+impl Scale {
+    fn fit(states: &[NavigationState]) -> Self {
+        let min_horizontal = states.iter().map(|s| s.horizontal).min().unwrap_or(0);
+        let max_horizontal = states.iter().map(|s| s.horizontal).max().unwrap_or(0);
+        let min_depth = states.iter().map(|s| s.depth).min().unwrap_or(0);
+        let max_depth = states.iter().map(|s| s.depth).max().unwrap_or(0);
+        Self {
+            min_horizontal,
+            min_depth,
+            horizontal_span: (max_horizontal - min_horizontal).max(1) as f64,
+            depth_span: (max_depth - min_depth).max(1) as f64
+        }
+    }
+
+    // Position of a state within a width by height area, as fractions of each dimension
+    fn apply(&self, state: &NavigationState, width: f64, height: f64) -> (f64, f64) {
+        (
+            (state.horizontal - self.min_horizontal) as f64 / self.horizontal_span * width,
+            (state.depth - self.min_depth) as f64 / self.depth_span * height
+        )
+    }
+}
+
+fn direction_colour(direction: &CourseDirection) -> &'static str {
+    match direction {
+        CourseDirection::Forward(_) => "#1f77b4",
+        CourseDirection::Up(_) => "#2ca02c",
+        CourseDirection::Down(_) => "#d62728"
+    }
+}
+
+fn direction_symbol(direction: &CourseDirection) -> char {
+    match direction {
+        CourseDirection::Forward(_) => '>',
+        CourseDirection::Up(_) => '^',
+        CourseDirection::Down(_) => 'v'
+    }
+}
+
+// Draws the route of a course under each requested model (false for simple, true for aim), overlaid on one set of
+// axes. Segments are coloured by command type, and the aim model route is dashed to tell the two apart.
+pub fn render_svg(operations: &[CourseDirection], models: &[bool], width: f64, height: f64) -> String {
+    let routes: Vec<(bool, Vec<NavigationState>)> = models
+        .iter()
+        .map(|&with_aim| (with_aim, trajectory(operations, with_aim, NavigationState::default())))
+        .collect();
+    let all_states: Vec<NavigationState> = routes.iter().flat_map(|(_, states)| states.iter().copied()).collect();
+    let scale = Scale::fit(&all_states);
+    let plot_width = width - 2.0 * SVG_MARGIN;
+    let plot_height = height - 2.0 * SVG_MARGIN;
+
+    let mut svg = String::new();
+    writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">", width, height, width, height).unwrap();
+    writeln!(svg, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>").unwrap();
+    for (with_aim, states) in routes.iter() {
+        let dash = if *with_aim { " stroke-dasharray=\"6 3\"" } else { "" };
+        writeln!(svg, "<g id=\"{}\" stroke-width=\"2\" fill=\"none\"{}>", navigation_model_name(*with_aim), dash).unwrap();
+        for (direction, segment) in operations.iter().zip(states.windows(2)) {
+            let (x1, y1) = scale.apply(&segment[0], plot_width, plot_height);
+            let (x2, y2) = scale.apply(&segment[1], plot_width, plot_height);
+            writeln!(
+                svg,
+                "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\"/>",
+                x1 + SVG_MARGIN, y1 + SVG_MARGIN, x2 + SVG_MARGIN, y2 + SVG_MARGIN, direction_colour(direction)
+            ).unwrap();
+        }
+        writeln!(svg, "</g>").unwrap();
+    }
+    let legend = [("forward", CourseDirection::Forward(0)), ("up", CourseDirection::Up(0)), ("down", CourseDirection::Down(0))];
+    for (i, (name, direction)) in legend.iter().enumerate() {
+        writeln!(svg, "<text x=\"{}\" y=\"{}\" font-size=\"12\" fill=\"{}\">{}</text>", width - 70.0, 15.0 * (i + 1) as f64, direction_colour(direction), name).unwrap();
+    }
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+// Draws the route of a course as text, scaled to fit the given number of columns and rows. Each segment is drawn
+// with the symbol of its command (> forward, ^ up, v down), with S marking the start and E the end.
+pub fn render_ascii_map(operations: &[CourseDirection], with_aim: bool, columns: usize, rows: usize) -> String {
+    let states = trajectory(operations, with_aim, NavigationState::default());
+    let scale = Scale::fit(&states);
+    let max_column = columns.saturating_sub(1) as f64;
+    let max_row = rows.saturating_sub(1) as f64;
+    let cell = |state: &NavigationState| {
+        let (x, y) = scale.apply(state, max_column, max_row);
+        (x.round(), y.round())
+    };
+
+    let mut map = vec![vec![' '; columns]; rows];
+    for (direction, segment) in operations.iter().zip(states.windows(2)) {
+        if segment[0] == segment[1] || columns == 0 || rows == 0 {
+            continue;
+        }
+        let (x1, y1) = cell(&segment[0]);
+        let (x2, y2) = cell(&segment[1]);
+        let steps = (x2 - x1).abs().max((y2 - y1).abs()) as usize;
+        for step in 0..=steps {
+            let t = if steps == 0 { 0.0 } else { step as f64 / steps as f64 };
+            let x = (x1 + (x2 - x1) * t).round() as usize;
+            let y = (y1 + (y2 - y1) * t).round() as usize;
+            map[y][x] = direction_symbol(direction);
+        }
+    }
+
+    if columns > 0 && rows > 0 {
+        let (x, y) = cell(&states[states.len() - 1]);
+        map[y as usize][x as usize] = 'E';
+        let (x, y) = cell(&states[0]);
+        map[y as usize][x as usize] = 'S';
+    }
+
+    map.iter()
+        .map(|row| row.iter().collect::<String>().trim_end().to_string())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::course::CourseDirection;
+    use super::render_ascii_map;
+    use super::render_svg;
+
+    #[test]
+    fn render_ascii_map_draws_scaled_route() {
+        // arrange
+        let course = vec![CourseDirection::Down(2), CourseDirection::Forward(4)];
+        // act
+        let result = render_ascii_map(&course, false, 5, 3);
+        // assert
+        assert_eq!("S\nv\n>>>>E", result);
+    }
+
+    #[test]
+    fn render_ascii_map_scales_sloped_aim_segments() {
+        // arrange
+        let course = vec![CourseDirection::Down(1), CourseDirection::Forward(20)];
+        // act
+        let result = render_ascii_map(&course, true, 5, 5);
+        // assert
+        assert_eq!("S\n >\n  >\n   >\n    E", result);
+    }
+
+    #[test]
+    fn render_svg_colours_segments_by_command() {
+        // arrange
+        let course = vec![CourseDirection::Down(2), CourseDirection::Forward(4), CourseDirection::Up(1)];
+        // act
+        let result = render_svg(&course, &[false], 200.0, 100.0);
+        // assert
+        assert!(result.starts_with("<svg"));
+        assert_eq!(3, result.matches("<line").count());
+        assert!(result.contains("x1=\"20.00\" y1=\"20.00\" x2=\"20.00\" y2=\"80.00\" stroke=\"#d62728\""));
+        assert!(result.contains("stroke=\"#1f77b4\""));
+        assert!(result.contains("stroke=\"#2ca02c\""));
+    }
+
+    #[test]
+    fn render_svg_overlays_both_models() {
+        // arrange
+        let course = vec![CourseDirection::Down(2), CourseDirection::Forward(4)];
+        // act
+        let result = render_svg(&course, &[false, true], 200.0, 100.0);
+        // assert
+        assert_eq!(4, result.matches("<line").count());
+        assert!(result.contains("<g id=\"simple\""));
+        assert!(result.contains("<g id=\"aim\" stroke-width=\"2\" fill=\"none\" stroke-dasharray=\"6 3\">"));
+    }
+}
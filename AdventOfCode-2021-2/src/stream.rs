@@ -0,0 +1,132 @@
+use crate::course::{parse_course_string, CourseDirection, ParseCourseError};
+use crate::navigation::NavigationState;
+use std::fmt;
+use std::io::{self, BufRead};
+
+#[derive(Debug)]
+pub enum StreamError {
+    // When a line can't be read. First is line number, second is the underlying error
+    Io(usize, io::Error),
+    // When a line isn't a valid course direction
+    Parse(ParseCourseError)
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Io(line_num, e) => write!(f, "Line {}: {}", line_num + 1, e),
+            StreamError::Parse(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+// Reads course directions one line at a time, reusing a single line buffer, so a course of any length can be
+// followed without holding it in memory. Lines are trimmed and parsed exactly as parse_course does.
+pub struct CourseReader<R: BufRead> {
+    reader: R,
+    line: String,
+    line_num: usize,
+    failed: bool
+}
+
+impl<R: BufRead> CourseReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, line: String::new(), line_num: 0, failed: false }
+    }
+}
+
+impl<R: BufRead> Iterator for CourseReader<R> {
+    type Item = Result<CourseDirection, StreamError>;
+
+    // Stops after the first error, as there's no telling where the next line starts after a failed read
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        self.line.clear();
+        let line_num = self.line_num;
+        let result = match self.reader.read_line(&mut self.line) {
+            Ok(0) => return None,
+            Ok(_) => parse_course_string(line_num, self.line.trim()).map_err(StreamError::Parse),
+            Err(e) => Err(StreamError::Io(line_num, e))
+        };
+        self.line_num += 1;
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+// Follows a course from a reader under each requested model (false for simple, true for aim) in a single pass,
+// returning the final state for each model in the same order
+pub fn navigate_stream<R: BufRead>(reader: R, models: &[bool]) -> Result<Vec<NavigationState>, StreamError> {
+    let mut states = vec![NavigationState::default(); models.len()];
+    for op in CourseReader::new(reader) {
+        let op = op?;
+        for (state, &with_aim) in states.iter_mut().zip(models.iter()) {
+            state.apply(&op, with_aim);
+        }
+    }
+    Ok(states)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::course::{parse_course, CourseDirection, ParseCourseError};
+    use crate::navigation::navigate;
+    use super::navigate_stream;
+    use super::CourseReader;
+    use super::StreamError;
+
+    const EXAMPLE: &str = "forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2\n";
+
+    #[test]
+    fn course_reader_parses_each_line() {
+        // arrange
+        let input = "forward 5\r\n  down 2 \nup 1";
+        // act
+        let result: Vec<CourseDirection> = CourseReader::new(input.as_bytes()).map(|op| op.unwrap()).collect();
+        // assert
+        assert_eq!(result, vec![CourseDirection::Forward(5), CourseDirection::Down(2), CourseDirection::Up(1)]);
+    }
+
+    #[test]
+    fn course_reader_reports_exact_line_and_stops() {
+        // arrange
+        let input = "forward 5\ndown 5\nsideways 3\nup 1\n";
+        // act
+        let result: Vec<_> = CourseReader::new(input.as_bytes()).collect();
+        // assert
+        assert_eq!(3, result.len());
+        match &result[2] {
+            Err(StreamError::Parse(e)) => assert_eq!(&ParseCourseError::InvalidCourseString(2, "sideways".to_string()), e),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn navigate_stream_matches_batch_navigation() {
+        // arrange
+        let lines: Vec<String> = EXAMPLE.lines().map(|l| l.to_string()).collect();
+        let course = parse_course(&lines).unwrap();
+        // act
+        let result = navigate_stream(EXAMPLE.as_bytes(), &[false, true]).unwrap();
+        // assert
+        assert_eq!(navigate(&course, false), result[0].coordinates());
+        assert_eq!(navigate(&course, true), result[1].coordinates());
+    }
+
+    #[test]
+    fn navigate_stream_fails_with_batch_parse_error() {
+        // arrange
+        let input = "forward 5\n\nup 1\n";
+        let lines: Vec<String> = input.lines().map(|l| l.to_string()).collect();
+        // act
+        let result = navigate_stream(input.as_bytes(), &[false]);
+        // assert
+        match result {
+            Err(StreamError::Parse(e)) => assert_eq!(parse_course(&lines).unwrap_err(), e),
+            _ => panic!()
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use crate::course::CourseDirection;
+use crate::navigation::{navigation_model_name, trajectory, NavigationState};
+use std::fmt;
+
+// How many commands of one type a course has, and the sum of their amounts
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct CommandTotals {
+    pub count: usize,
+    pub total: i64
+}
+
+// The most extreme value reached, and the command that first reached it. None means it was the starting value.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Extreme {
+    pub value: i32,
+    pub command_index: Option<usize>
+}
+
+// A run of consecutive forward commands, starting at a command index
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ForwardRun {
+    pub start: usize,
+    pub length: usize
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CourseSummary {
+    pub with_aim: bool,
+    pub commands: usize,
+    pub forward: CommandTotals,
+    pub up: CommandTotals,
+    pub down: CommandTotals,
+    pub max_depth: Extreme,
+    pub min_depth: Extreme,
+    pub max_aim: Extreme,
+    pub min_aim: Extreme,
+    // Length of the route, following each command's straight line segment
+    pub distance: f64,
+    // The first of the longest runs, or None if there are no forward commands
+    pub longest_forward_run: Option<ForwardRun>
+}
+
+pub fn summarize_course(operations: &[CourseDirection], with_aim: bool) -> CourseSummary {
+    let states = trajectory(operations, with_aim, NavigationState::default());
+    let (mut forward, mut up, mut down) = (CommandTotals::default(), CommandTotals::default(), CommandTotals::default());
+    let mut longest_forward_run: Option<ForwardRun> = None;
+    let mut current_run: Option<ForwardRun> = None;
+    for (index, op) in operations.iter().enumerate() {
+        let (totals, n) = match op {
+            CourseDirection::Forward(n) => (&mut forward, n),
+            CourseDirection::Up(n) => (&mut up, n),
+            CourseDirection::Down(n) => (&mut down, n)
+        };
+        totals.count += 1;
+        totals.total += *n as i64;
+
+        current_run = match (op, current_run) {
+            (CourseDirection::Forward(_), Some(run)) => Some(ForwardRun { start: run.start, length: run.length + 1 }),
+            (CourseDirection::Forward(_), None) => Some(ForwardRun { start: index, length: 1 }),
+            _ => None
+        };
+        if let Some(run) = current_run.filter(|run| longest_forward_run.is_none_or(|longest| run.length > longest.length)) {
+            longest_forward_run = Some(run);
+        }
+    }
+
+    let distance = states
+        .windows(2)
+        .map(|segment| {
+            let horizontal = (segment[1].horizontal - segment[0].horizontal) as f64;
+            let depth = (segment[1].depth - segment[0].depth) as f64;
+            horizontal.hypot(depth)
+        })
+        .sum();
+
+    CourseSummary {
+        with_aim,
+        commands: operations.len(),
+        forward,
+        up,
+        down,
+        max_depth: extreme(&states, |s| s.depth, |value, best| value > best),
+        min_depth: extreme(&states, |s| s.depth, |value, best| value < best),
+        max_aim: extreme(&states, |s| s.aim, |value, best| value > best),
+        min_aim: extreme(&states, |s| s.aim, |value, best| value < best),
+        distance,
+        longest_forward_run
+    }
+}
+
+// The first state whose value beats every other, where states[0] is the start and states[i + 1] follows command i
+fn extreme(states: &[NavigationState], value: fn(&NavigationState) -> i32, beats: fn(i32, i32) -> bool) -> Extreme {
+    let mut best = Extreme { value: value(&states[0]), command_index: None };
+    for (index, state) in states.iter().enumerate().skip(1) {
+        if beats(value(state), best.value) {
+            best = Extreme { value: value(state), command_index: Some(index - 1) };
+        }
+    }
+    best
+}
+
+fn command_index_text(index: Option<usize>) -> String {
+    match index {
+        Some(index) => format!("after command {}", index + 1),
+        None => "at the start".to_string()
+    }
+}
+
+fn command_index_json(index: Option<usize>) -> String {
+    match index {
+        Some(index) => index.to_string(),
+        None => "null".to_string()
+    }
+}
+
+impl CourseSummary {
+    // A single line JSON object. Command indexes count from 0, with null for the starting state.
+    pub fn to_json(&self) -> String {
+        let totals = |t: &CommandTotals| format!("{{\"count\":{},\"total\":{}}}", t.count, t.total);
+        let extreme = |e: &Extreme| format!("{{\"value\":{},\"command_index\":{}}}", e.value, command_index_json(e.command_index));
+        let run = match self.longest_forward_run {
+            Some(run) => format!("{{\"start\":{},\"length\":{}}}", run.start, run.length),
+            None => "null".to_string()
+        };
+        format!(
+            "{{\"model\":\"{}\",\"commands\":{},\"forward\":{},\"up\":{},\"down\":{},\"max_depth\":{},\"min_depth\":{},\"max_aim\":{},\"min_aim\":{},\"distance\":{},\"longest_forward_run\":{}}}",
+            navigation_model_name(self.with_aim), self.commands, totals(&self.forward), totals(&self.up), totals(&self.down),
+            extreme(&self.max_depth), extreme(&self.min_depth), extreme(&self.max_aim), extreme(&self.min_aim),
+            self.distance, run
+        )
+    }
+}
+
+impl fmt::Display for CourseSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Model: {}", navigation_model_name(self.with_aim))?;
+        writeln!(f, "Commands: {}", self.commands)?;
+        for (name, totals) in [("forward", &self.forward), ("up", &self.up), ("down", &self.down)] {
+            writeln!(f, "  {}: {} command(s), total {}", name, totals.count, totals.total)?;
+        }
+        for (name, extreme) in [("Maximum depth", &self.max_depth), ("Minimum depth", &self.min_depth), ("Maximum aim", &self.max_aim), ("Minimum aim", &self.min_aim)] {
+            writeln!(f, "{}: {} ({})", name, extreme.value, command_index_text(extreme.command_index))?;
+        }
+        writeln!(f, "Distance travelled: {:.2}", self.distance)?;
+        match self.longest_forward_run {
+            Some(run) => write!(f, "Longest forward run: {} command(s) from command {}", run.length, run.start + 1),
+            None => write!(f, "Longest forward run: none")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::course::CourseDirection;
+    use super::summarize_course;
+    use super::CommandTotals;
+    use super::Extreme;
+    use super::ForwardRun;
+
+    fn example_course() -> Vec<CourseDirection> {
+        vec![
+            CourseDirection::Forward(5),
+            CourseDirection::Down(5),
+            CourseDirection::Forward(8),
+            CourseDirection::Up(3),
+            CourseDirection::Down(8),
+            CourseDirection::Forward(2),
+        ]
+    }
+
+    #[test]
+    fn summarize_course_totals_each_command_type() {
+        // act
+        let result = summarize_course(&example_course(), false);
+        // assert
+        assert_eq!(6, result.commands);
+        assert_eq!(CommandTotals { count: 3, total: 15 }, result.forward);
+        assert_eq!(CommandTotals { count: 1, total: 3 }, result.up);
+        assert_eq!(CommandTotals { count: 2, total: 13 }, result.down);
+    }
+
+    #[test]
+    fn summarize_course_finds_extremes_and_where_they_occur() {
+        // act
+        let result = summarize_course(&example_course(), true);
+        // assert
+        assert_eq!(Extreme { value: 60, command_index: Some(5) }, result.max_depth);
+        assert_eq!(Extreme { value: 0, command_index: None }, result.min_depth);
+        assert_eq!(Extreme { value: 10, command_index: Some(4) }, result.max_aim);
+        assert_eq!(Extreme { value: 0, command_index: None }, result.min_aim);
+    }
+
+    #[test]
+    fn summarize_course_measures_distance_along_segments() {
+        // arrange
+        let course = vec![CourseDirection::Down(1), CourseDirection::Forward(4), CourseDirection::Up(2)];
+        // act
+        let simple = summarize_course(&course, false);
+        let aim = summarize_course(&course, true);
+        // assert
+        assert!((simple.distance - 7.0).abs() < 1e-9);
+        assert!((aim.distance - 32f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn summarize_course_finds_first_longest_forward_run() {
+        // arrange
+        let course = vec![
+            CourseDirection::Forward(1),
+            CourseDirection::Forward(1),
+            CourseDirection::Up(1),
+            CourseDirection::Forward(1),
+            CourseDirection::Forward(1),
+        ];
+        // act
+        let result = summarize_course(&course, false);
+        // assert
+        assert_eq!(Some(ForwardRun { start: 0, length: 2 }), result.longest_forward_run);
+        assert_eq!(None, summarize_course(&[CourseDirection::Up(1)], false).longest_forward_run);
+    }
+
+    #[test]
+    fn course_summary_writes_json() {
+        // arrange
+        let course = vec![CourseDirection::Down(2), CourseDirection::Forward(3)];
+        // act
+        let result = summarize_course(&course, false).to_json();
+        // assert
+        assert_eq!(
+            "{\"model\":\"simple\",\"commands\":2,\"forward\":{\"count\":1,\"total\":3},\"up\":{\"count\":0,\"total\":0},\
+             \"down\":{\"count\":1,\"total\":2},\"max_depth\":{\"value\":2,\"command_index\":0},\
+             \"min_depth\":{\"value\":0,\"command_index\":null},\"max_aim\":{\"value\":0,\"command_index\":null},\
+             \"min_aim\":{\"value\":0,\"command_index\":null},\"distance\":5,\"longest_forward_run\":{\"start\":1,\"length\":1}}",
+            result
+        );
+    }
+}
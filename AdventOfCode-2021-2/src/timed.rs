@@ -0,0 +1,375 @@
+use crate::course::{parse_course_string, CourseDirection, ParseCourseError};
+use std::fmt;
+
+// A course direction carried out over time. Magnitude is covered at a constant speed, in units per unit of time.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TimedCommand {
+    pub start: f64,
+    pub direction: CourseDirection,
+    pub speed: f64
+}
+
+impl TimedCommand {
+    pub fn duration(&self) -> f64 {
+        magnitude(&self.direction).abs() as f64 / self.speed
+    }
+
+    pub fn end(&self) -> f64 {
+        self.start + self.duration()
+    }
+}
+
+// Position part way through a timed course. Commands can be part done, so coordinates aren't whole numbers.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct TimedState {
+    pub time: f64,
+    pub depth: f64,
+    pub horizontal: f64,
+    pub aim: f64
+}
+
+impl fmt::Display for TimedState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "t={}: Depth: {}, Horizontal: {}, Aim: {}", self.time, self.depth, self.horizontal, self.aim)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseTimedCourseError {
+    // When the direction part of the line isn't valid course syntax
+    InvalidCourse(ParseCourseError),
+    // When the t= start time can't be parsed. Second value is the unparseable time.
+    UnparseableTime(usize, String),
+    // When the t= start time is negative or not finite. Second value is the invalid time.
+    InvalidTime(usize, String),
+    // When the speed can't be parsed or isn't a positive, finite number. Second value is the invalid speed.
+    InvalidSpeed(usize, String),
+    // When anything other than "@ speed <number>" follows the direction. Second value is the full string
+    UnexpectedTokens(usize, String),
+    // When a command starts before the previous one has finished. Second value is when the previous one ends.
+    OverlappingCommand(usize, f64)
+}
+
+impl fmt::Display for ParseTimedCourseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseTimedCourseError::InvalidCourse(e) => write!(f, "{}", e),
+            ParseTimedCourseError::UnparseableTime(line_num, time) => write!(f, "Line {}: could not parse time \"{}\"", line_num + 1, time),
+            ParseTimedCourseError::InvalidTime(line_num, time) => write!(f, "Line {}: time \"{}\" is not a finite, non-negative number", line_num + 1, time),
+            ParseTimedCourseError::InvalidSpeed(line_num, speed) => write!(f, "Line {}: speed \"{}\" is not a positive number", line_num + 1, speed),
+            ParseTimedCourseError::UnexpectedTokens(line_num, line) => write!(f, "Line {}: expected \"[t=<time>] <direction> <number> [@ speed <number>]\", got \"{}\"", line_num + 1, line),
+            ParseTimedCourseError::OverlappingCommand(line_num, previous_end) => write!(f, "Line {}: starts before the previous command ends at t={}", line_num + 1, previous_end)
+        }
+    }
+}
+
+// A time step that isn't a positive, finite number, which would never move the simulation forward. Value is the step.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct InvalidTimeStep(pub f64);
+
+impl fmt::Display for InvalidTimeStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "time step {} is not a positive number", self.0)
+    }
+}
+
+pub fn check_time_step(time_step: f64) -> Result<f64, InvalidTimeStep> {
+    if time_step.is_finite() && time_step > 0.0 {
+        Ok(time_step)
+    } else {
+        Err(InvalidTimeStep(time_step))
+    }
+}
+
+// Timed course lines look like "t=12.5 forward 3 @ speed 1.2". Without a start time a command begins as soon as the
+// previous one ends, and without a speed it moves at 1 unit per unit of time.
+pub fn parse_timed_course(input: &[String]) -> Result<Vec<TimedCommand>, ParseTimedCourseError> {
+    let mut commands: Vec<TimedCommand> = Vec::with_capacity(input.len());
+    for (line_num, line_str) in input.iter().enumerate() {
+        let previous_end = commands.last().map_or(0.0, |c| c.end());
+        let command = parse_timed_course_string(line_num, line_str, previous_end)?;
+        if command.start < previous_end {
+            return Err(ParseTimedCourseError::OverlappingCommand(line_num, previous_end));
+        }
+        commands.push(command);
+    }
+
+    Ok(commands)
+}
+
+fn parse_timed_course_string(line_num: usize, input: &str, previous_end: f64) -> Result<TimedCommand, ParseTimedCourseError> {
+    let mut tokens: &[&str] = &input.split_whitespace().collect::<Vec<&str>>();
+
+    let start = match tokens.first().and_then(|t| t.strip_prefix("t=")) {
+        Some(time) => {
+            tokens = &tokens[1..];
+            match time.parse::<f64>() {
+                Ok(t) if t.is_finite() && t >= 0.0 => t,
+                Ok(_) => return Err(ParseTimedCourseError::InvalidTime(line_num, time.to_string())),
+                Err(_) => return Err(ParseTimedCourseError::UnparseableTime(line_num, time.to_string()))
+            }
+        },
+        None => previous_end
+    };
+
+    let direction_end = tokens.len().min(2);
+    let direction = parse_course_string(line_num, &tokens[..direction_end].join(" ")).map_err(ParseTimedCourseError::InvalidCourse)?;
+
+    let speed = match tokens[direction_end..] {
+        [] => 1.0,
+        ["@", "speed", speed] => match speed.parse::<f64>() {
+            Ok(s) if s.is_finite() && s > 0.0 => s,
+            _ => return Err(ParseTimedCourseError::InvalidSpeed(line_num, speed.to_string()))
+        },
+        _ => return Err(ParseTimedCourseError::UnexpectedTokens(line_num, input.to_string()))
+    };
+
+    Ok(TimedCommand { start, direction, speed })
+}
+
+pub fn mission_duration(course: &[TimedCommand]) -> f64 {
+    course.last().map_or(0.0, |c| c.end())
+}
+
+// Advances a timed course through time, carrying out whatever share of each command falls in the elapsed time
+pub struct TimedSimulator<'a> {
+    course: &'a [TimedCommand],
+    with_aim: bool,
+    // The first command that hasn't been fully carried out yet
+    next_command: usize,
+    state: TimedState
+}
+
+impl<'a> TimedSimulator<'a> {
+    pub fn new(course: &'a [TimedCommand], with_aim: bool) -> Self {
+        Self {
+            course,
+            with_aim,
+            next_command: 0,
+            state: TimedState::default()
+        }
+    }
+
+    pub fn state(&self) -> TimedState {
+        self.state
+    }
+
+    pub fn step(&mut self, time_step: f64) -> Result<TimedState, InvalidTimeStep> {
+        Ok(self.advance_to(self.state.time + check_time_step(time_step)?))
+    }
+
+    // Moves forward to the given time. Times earlier than the current one leave the state unchanged.
+    pub fn advance_to(&mut self, time: f64) -> TimedState {
+        while let Some(command) = self.course.get(self.next_command) {
+            if command.start >= time {
+                break;
+            }
+
+            let from = self.state.time.max(command.start);
+            let to = time.min(command.end());
+            if to > from {
+                self.apply_fraction(&command.direction, (to - from) / command.duration());
+            }
+
+            if command.end() > time {
+                break;
+            }
+            self.next_command += 1;
+        }
+
+        self.state.time = self.state.time.max(time);
+        self.state
+    }
+
+    fn apply_fraction(&mut self, direction: &CourseDirection, fraction: f64) {
+        let amount = magnitude(direction) as f64 * fraction;
+        match direction {
+            CourseDirection::Up(_) => {
+                if self.with_aim {
+                    self.state.aim -= amount;
+                } else {
+                    self.state.depth -= amount;
+                }
+            },
+            CourseDirection::Down(_) => {
+                if self.with_aim {
+                    self.state.aim += amount;
+                } else {
+                    self.state.depth += amount;
+                }
+            },
+            CourseDirection::Forward(_) => {
+                self.state.horizontal += amount;
+                if self.with_aim {
+                    self.state.depth += self.state.aim * amount;
+                }
+            }
+        }
+    }
+}
+
+// Samples the position every time step from the start of the mission, finishing with the position at the very end
+pub fn simulate(course: &[TimedCommand], with_aim: bool, time_step: f64) -> Result<Vec<TimedState>, InvalidTimeStep> {
+    check_time_step(time_step)?;
+    let duration = mission_duration(course);
+    let mut simulator = TimedSimulator::new(course, with_aim);
+    let mut samples = vec![simulator.state()];
+    let mut step = 1;
+    while (step as f64) * time_step < duration {
+        samples.push(simulator.advance_to(step as f64 * time_step));
+        step += 1;
+    }
+    samples.push(simulator.advance_to(duration));
+
+    Ok(samples)
+}
+
+// The exact position at any moment, interpolating through commands that are part done
+pub fn position_at(course: &[TimedCommand], with_aim: bool, time: f64) -> TimedState {
+    TimedSimulator::new(course, with_aim).advance_to(time)
+}
+
+fn magnitude(direction: &CourseDirection) -> i32 {
+    match direction {
+        CourseDirection::Forward(n) | CourseDirection::Up(n) | CourseDirection::Down(n) => *n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::course::{CourseDirection, ParseCourseError};
+    use super::mission_duration;
+    use super::parse_timed_course;
+    use super::position_at;
+    use super::simulate;
+    use super::ParseTimedCourseError;
+    use super::InvalidTimeStep;
+    use super::TimedCommand;
+    use super::TimedSimulator;
+    use super::TimedState;
+
+    fn lines(input: &[&str]) -> Vec<String> {
+        input.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_timed_course_parses_times_and_speeds() {
+        // arrange
+        let input = lines(&["t=12.5 forward 3 @ speed 1.5", "down 4", "t=20 up 2 @ speed 4"]);
+        // act
+        let result = parse_timed_course(&input).unwrap();
+        // assert
+        assert_eq!(result, vec![
+            TimedCommand { start: 12.5, direction: CourseDirection::Forward(3), speed: 1.5 },
+            TimedCommand { start: 14.5, direction: CourseDirection::Down(4), speed: 1.0 },
+            TimedCommand { start: 20.0, direction: CourseDirection::Up(2), speed: 4.0 },
+        ]);
+        assert_eq!(20.5, mission_duration(&result));
+    }
+
+    #[test]
+    fn parse_timed_course_rejects_bad_lines() {
+        assert_eq!(
+            parse_timed_course(&lines(&["t=soon forward 3"])),
+            Err(ParseTimedCourseError::UnparseableTime(0, "soon".to_string()))
+        );
+        assert_eq!(
+            parse_timed_course(&lines(&["forward 3 @ speed 0"])),
+            Err(ParseTimedCourseError::InvalidSpeed(0, "0".to_string()))
+        );
+        assert_eq!(
+            parse_timed_course(&lines(&["forward 3 quickly"])),
+            Err(ParseTimedCourseError::UnexpectedTokens(0, "forward 3 quickly".to_string()))
+        );
+        assert_eq!(
+            parse_timed_course(&lines(&["forward 3", "t=1 down 2"])),
+            Err(ParseTimedCourseError::OverlappingCommand(1, 3.0))
+        );
+        assert_eq!(
+            parse_timed_course(&lines(&["t=1 backward 2"])),
+            Err(ParseTimedCourseError::InvalidCourse(ParseCourseError::InvalidCourseString(0, "backward".to_string())))
+        );
+    }
+
+    #[test]
+    fn parse_timed_course_rejects_times_and_speeds_that_never_finish() {
+        assert_eq!(
+            parse_timed_course(&lines(&["t=inf forward 3"])),
+            Err(ParseTimedCourseError::InvalidTime(0, "inf".to_string()))
+        );
+        assert_eq!(
+            parse_timed_course(&lines(&["forward 1", "t=NaN forward 3"])),
+            Err(ParseTimedCourseError::InvalidTime(1, "NaN".to_string()))
+        );
+        assert_eq!(
+            parse_timed_course(&lines(&["t=-1 forward 3"])),
+            Err(ParseTimedCourseError::InvalidTime(0, "-1".to_string()))
+        );
+        assert_eq!(
+            parse_timed_course(&lines(&["forward 3 @ speed inf"])),
+            Err(ParseTimedCourseError::InvalidSpeed(0, "inf".to_string()))
+        );
+    }
+
+    #[test]
+    fn position_at_interpolates_part_done_commands() {
+        // arrange
+        let course = parse_timed_course(&lines(&["down 2 @ speed 2", "forward 4 @ speed 2"])).unwrap();
+        // act
+        let result = position_at(&course, true, 2.0);
+        // assert
+        assert_eq!(result, TimedState { time: 2.0, depth: 4.0, horizontal: 2.0, aim: 2.0 });
+    }
+
+    #[test]
+    fn position_at_holds_still_between_commands() {
+        // arrange
+        let course = parse_timed_course(&lines(&["forward 2", "t=10 down 3"])).unwrap();
+        // act
+        let result = position_at(&course, false, 6.0);
+        // assert
+        assert_eq!(result, TimedState { time: 6.0, depth: 0.0, horizontal: 2.0, aim: 0.0 });
+    }
+
+    #[test]
+    fn simulate_matches_navigate_at_the_end() {
+        // arrange
+        let course = parse_timed_course(&lines(&[
+            "forward 5", "down 5 @ speed 2", "forward 8 @ speed 3", "up 3", "down 8", "t=40 forward 2"
+        ])).unwrap();
+        // act
+        let result = simulate(&course, true, 0.7).unwrap();
+        // assert
+        assert_eq!(result[0], TimedState::default());
+        let last = result.last().unwrap();
+        assert_eq!(42.0, last.time);
+        assert!((last.depth - 60.0).abs() < 1e-9);
+        assert!((last.horizontal - 15.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn simulate_rejects_non_positive_time_steps() {
+        // arrange
+        let course = parse_timed_course(&lines(&["forward 5"])).unwrap();
+        // act/assert
+        assert_eq!(Err(InvalidTimeStep(0.0)), simulate(&course, false, 0.0));
+        assert_eq!(Err(InvalidTimeStep(-1.5)), simulate(&course, false, -1.5));
+        assert!(simulate(&course, false, f64::NAN).is_err());
+        assert!(simulate(&course, false, f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn timed_simulator_step_rejects_non_positive_time_steps() {
+        // arrange
+        let course = parse_timed_course(&lines(&["forward 5"])).unwrap();
+        let mut simulator = TimedSimulator::new(&course, false);
+        // act
+        let zero = simulator.step(0.0);
+        let negative = simulator.step(-2.0);
+        let forward = simulator.step(2.0);
+        // assert
+        assert_eq!(Err(InvalidTimeStep(0.0)), zero);
+        assert_eq!(Err(InvalidTimeStep(-2.0)), negative);
+        assert_eq!(Ok(TimedState { time: 2.0, depth: 0.0, horizontal: 2.0, aim: 0.0 }), forward);
+    }
+}
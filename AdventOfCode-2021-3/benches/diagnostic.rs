@@ -0,0 +1,32 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use advent_of_code_2021_3::diagnostic::{calculate_diagnostic_result, calculate_packed_diagnostic_result, diagnostic_grid_from_lines, packed_diagnostic_grid_from_lines, TiePolicy};
+use advent_of_code_2021_3::file::load_string_txt_to_vector;
+
+// The puzzle input repeated to reach each size, so the column majorities match the original file
+fn scaled_diagnostic(repeats: usize) -> Vec<String> {
+    let lines = load_string_txt_to_vector(&"./data/diagnostic.txt".to_string()).expect("Benchmarks need data/diagnostic.txt");
+    lines.iter().cycle().take(lines.len() * repeats).cloned().collect()
+}
+
+fn column_counting(c: &mut Criterion) {
+    let mut group = c.benchmark_group("diagnostic_result");
+    for repeats in [1, 100, 1000] {
+        let lines = scaled_diagnostic(repeats);
+        let grid = diagnostic_grid_from_lines(&lines).unwrap();
+        let packed = packed_diagnostic_grid_from_lines(&lines).unwrap();
+        let expected = calculate_diagnostic_result(&grid, TiePolicy::PreferOne).unwrap();
+        let result = calculate_packed_diagnostic_result(&packed, TiePolicy::PreferOne).unwrap();
+        assert_eq!((expected.gamma_rate, expected.epsilon_rate), (result.gamma_rate, result.epsilon_rate));
+
+        group.bench_with_input(BenchmarkId::new("transposed", lines.len()), &grid, |b, grid| {
+            b.iter(|| calculate_diagnostic_result(grid, TiePolicy::PreferOne))
+        });
+        group.bench_with_input(BenchmarkId::new("packed", lines.len()), &packed, |b, packed| {
+            b.iter(|| calculate_packed_diagnostic_result(packed, TiePolicy::PreferOne))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, column_counting);
+criterion_main!(benches);
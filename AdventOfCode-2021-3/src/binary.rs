@@ -1,27 +1,414 @@
-pub fn binary_to_number(binary: &Vec<bool>) -> u32 {
-    binary.iter().fold(0, |acc, &b| (acc << 1) | b as u32)
+use crate::wide::WideNumber;
+use std::ops::{BitAnd, BitXor, Not, Shl, Shr};
+
+// Which end of a bit slice holds the most significant bit
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum BitOrder {
+    // As diagnostic rows are written
+    #[default]
+    MsbFirst,
+    LsbFirst
+}
+
+// The unsigned integer types a row of bits can be read into
+pub trait BinaryNumber:
+    Copy + Default + Eq
+    + Not<Output = Self> + BitAnd<Output = Self> + BitXor<Output = Self>
+    + Shl<u32, Output = Self> + Shr<u32, Output = Self>
+{
+    const BITS: u32;
+    fn from_bool(bit: bool) -> Self;
+    // Bits from the highest one down, with none for zero
+    fn significant_bits(self) -> u32;
+
+    // Shifts in one more bit at the least significant end
+    fn push_bit(self, bit: bool) -> Self {
+        (self << 1) ^ Self::from_bool(bit)
+    }
+
+    // Bit index counts from the least significant end
+    fn bit(self, index: u32) -> bool {
+        (self >> index) & Self::from_bool(true) != Self::default()
+    }
+
+    // Ones in the lowest width bits
+    fn low_mask(width: u32) -> Self {
+        if width >= Self::BITS {
+            !Self::default()
+        } else {
+            !(!Self::default() << width)
+        }
+    }
+}
+
+macro_rules! impl_binary_number {
+    ($($t:ty),*) => {
+        $(
+            impl BinaryNumber for $t {
+                const BITS: u32 = <$t>::BITS;
+
+                fn from_bool(bit: bool) -> Self {
+                    bit as $t
+                }
+
+                fn significant_bits(self) -> u32 {
+                    <$t>::BITS - self.leading_zeros()
+                }
+            }
+        )*
+    };
+}
+
+impl_binary_number!(u8, u16, u32, u64, u128);
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct BinaryOverflowError {
+    // Bits from the highest one down, which is more than the type can hold
+    pub significant_bits: usize,
+    pub capacity: u32
+}
+
+// Reads bits most significant first. Leading zeros are fine however many there are, but a number too big for
+// the type is an error rather than losing its top bits.
+pub fn binary_to_number<T: BinaryNumber>(binary: &[bool]) -> Result<T, BinaryOverflowError> {
+    let significant_bits = binary.iter().skip_while(|b| !**b).count();
+    if significant_bits > T::BITS as usize {
+        return Err(BinaryOverflowError { significant_bits, capacity: T::BITS });
+    }
+    Ok(binary.iter().fold(T::default(), |acc, &b| acc.push_bit(b)))
+}
+
+pub fn binary_to_number_ordered<T: BinaryNumber>(binary: &[bool], order: BitOrder) -> Result<T, BinaryOverflowError> {
+    match order {
+        BitOrder::MsbFirst => binary_to_number(binary),
+        BitOrder::LsbFirst => binary_to_number(&binary.iter().rev().copied().collect::<Vec<bool>>())
+    }
+}
+
+// Reads bits most significant first into a number of any size
+pub fn binary_to_wide_number(binary: &[bool]) -> WideNumber {
+    WideNumber::from_bits(binary)
+}
+
+// Writes a number as exactly width bits, which is an error if the number needs more
+pub fn number_to_binary<T: BinaryNumber>(n: T, width: usize, order: BitOrder) -> Result<Vec<bool>, BinaryOverflowError> {
+    let significant_bits = n.significant_bits() as usize;
+    if significant_bits > width {
+        return Err(BinaryOverflowError { significant_bits, capacity: width as u32 });
+    }
+    let lsb_first = (0..width).map(|i| i < T::BITS as usize && n.bit(i as u32));
+    Ok(match order {
+        BitOrder::MsbFirst => lsb_first.rev().collect(),
+        BitOrder::LsbFirst => lsb_first.collect()
+    })
+}
+
+/*
+ * Bitwise operations
+ */
+pub fn complement(bits: &[bool]) -> Vec<bool> {
+    bits.iter().map(|b| !b).collect()
+}
+
+// Flips only the lowest width bits, as the complement of a width bit row would, rather than every bit of the type
+pub fn complement_number<T: BinaryNumber>(n: T, width: u32) -> T {
+    !n & T::low_mask(width)
+}
+
+// Each bit after the first is replaced by whether it differs from the bit before it (in most significant first
+// order), so consecutive numbers differ in a single bit
+pub fn gray_encode(bits: &[bool]) -> Vec<bool> {
+    let mut previous = false;
+    bits.iter().map(|&b| {
+        let encoded = b ^ previous;
+        previous = b;
+        encoded
+    }).collect()
+}
+
+pub fn gray_decode(gray: &[bool]) -> Vec<bool> {
+    let mut previous = false;
+    gray.iter().map(|&g| {
+        previous ^= g;
+        previous
+    }).collect()
+}
+
+pub fn gray_encode_number<T: BinaryNumber>(n: T) -> T {
+    n ^ (n >> 1)
+}
+
+pub fn gray_decode_number<T: BinaryNumber>(gray: T) -> T {
+    let mut n = gray;
+    let mut shifted = gray >> 1;
+    while shifted != T::default() {
+        n = n ^ shifted;
+        shifted = shifted >> 1;
+    }
+    n
+}
+
+// The number of ones
+pub fn hamming_weight(bits: &[bool]) -> usize {
+    bits.iter().filter(|b| **b).count()
+}
+
+// The number of positions where two rows differ, or None when they have different lengths
+pub fn hamming_distance(a: &[bool], b: &[bool]) -> Option<usize> {
+    if a.len() != b.len() {
+        return None;
+    }
+    Some(a.iter().zip(b.iter()).filter(|(x, y)| x != y).count())
+}
+
+/*
+ * Text conversions
+ */
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Radix {
+    Binary,
+    Octal,
+    Hex
+}
+
+impl Radix {
+    pub fn bits_per_digit(&self) -> usize {
+        match self {
+            Radix::Binary => 1,
+            Radix::Octal => 3,
+            Radix::Hex => 4
+        }
+    }
+
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Radix::Binary => "0b",
+            Radix::Octal => "0o",
+            Radix::Hex => "0x"
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ParseBitsError {
+    // When the text has no digits after its prefix
+    Empty,
+    // When a character isn't a digit of the radix. Value is the character.
+    InvalidDigit(char),
+    // When the value needs more bits than the requested width
+    Overflow(BinaryOverflowError)
+}
+
+// Writes bits most significant first with a 0b, 0o or 0x prefix. Leading zero bits are padded out to whole digits,
+// so the text keeps the row's width where the radix allows.
+pub fn format_bits(bits: &[bool], radix: Radix) -> String {
+    let per_digit = radix.bits_per_digit();
+    let padding = (per_digit - bits.len() % per_digit) % per_digit;
+    let padded: Vec<bool> = std::iter::repeat_n(false, padding).chain(bits.iter().copied()).collect();
+    let digits: String = padded
+        .chunks(per_digit)
+        .map(|digit| {
+            let value = digit.iter().fold(0u32, |acc, &b| (acc << 1) | b as u32);
+            std::char::from_digit(value, 1 << per_digit).expect("Digit is within its radix")
+        })
+        .collect();
+    format!("{}{}", radix.prefix(), digits)
+}
+
+// Reads text with a 0b, 0o or 0x prefix, or plain binary digits as in a diagnostic row. Each digit gives its full
+// number of bits unless a width is given, in which case the value is fitted to exactly that many bits.
+pub fn parse_bits(text: &str, width: Option<usize>) -> Result<Vec<bool>, ParseBitsError> {
+    let (radix, digits) = [Radix::Binary, Radix::Octal, Radix::Hex]
+        .iter()
+        .find_map(|radix| text.strip_prefix(radix.prefix()).map(|digits| (*radix, digits)))
+        .unwrap_or((Radix::Binary, text));
+    if digits.is_empty() {
+        return Err(ParseBitsError::Empty);
+    }
+
+    let per_digit = radix.bits_per_digit();
+    let mut bits = Vec::with_capacity(digits.len() * per_digit);
+    for c in digits.chars() {
+        let value = c.to_digit(1 << per_digit).ok_or(ParseBitsError::InvalidDigit(c))?;
+        bits.extend((0..per_digit).rev().map(|i| (value >> i) & 1 == 1));
+    }
+
+    match width {
+        None => Ok(bits),
+        Some(width) => {
+            let significant_bits = bits.iter().skip_while(|b| !**b).count();
+            if significant_bits > width {
+                return Err(ParseBitsError::Overflow(BinaryOverflowError { significant_bits, capacity: width as u32 }));
+            }
+            Ok(std::iter::repeat_n(false, width - significant_bits).chain(bits[bits.len() - significant_bits..].iter().copied()).collect())
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::binary_to_number;
+    use super::binary_to_number_ordered;
+    use super::binary_to_wide_number;
+    use super::complement;
+    use super::complement_number;
+    use super::format_bits;
+    use super::gray_decode;
+    use super::gray_decode_number;
+    use super::gray_encode;
+    use super::gray_encode_number;
+    use super::hamming_distance;
+    use super::hamming_weight;
+    use super::number_to_binary;
+    use super::parse_bits;
+    use super::BinaryOverflowError;
+    use super::BitOrder;
+    use super::ParseBitsError;
+    use super::Radix;
+    use crate::wide::WideNumber;
+
     #[test]
     fn binary_to_number_empty_is_zero() {
-        assert_eq!(0, binary_to_number(&Vec::new()));
+        assert_eq!(Ok(0), binary_to_number::<u32>(&Vec::new()));
     }
 
     #[test]
     fn binary_to_number_zero_is_zero() {
-        assert_eq!(0, binary_to_number(&vec![false, false, false, false]));
+        assert_eq!(Ok(0), binary_to_number::<u32>(&[false, false, false, false]));
     }
 
     #[test]
     fn binary_to_number_one_is_one() {
-        assert_eq!(1, binary_to_number(&vec![false, false, false, true]));
+        assert_eq!(Ok(1), binary_to_number::<u32>(&[false, false, false, true]));
     }
 
     #[test]
     fn binary_to_number_nine_is_nine() {
-        assert_eq!(9, binary_to_number(&vec![false, true, false, false, true]));
+        assert_eq!(Ok(9), binary_to_number::<u32>(&[false, true, false, false, true]));
+    }
+
+    #[test]
+    fn binary_to_number_allows_leading_zeros_beyond_width() {
+        // arrange
+        let mut bits = vec![false; 12];
+        bits.extend([true; 8]);
+        // act/assert
+        assert_eq!(Ok(255), binary_to_number::<u8>(&bits));
+    }
+
+    #[test]
+    fn binary_to_number_overflow_is_error() {
+        // arrange
+        let bits = vec![true; 33];
+        // act/assert
+        assert_eq!(Err(BinaryOverflowError { significant_bits: 33, capacity: 32 }), binary_to_number::<u32>(&bits));
+        assert_eq!(Ok((1u64 << 33) - 1), binary_to_number::<u64>(&bits));
+    }
+
+    #[test]
+    fn binary_to_number_fills_u128() {
+        // arrange
+        let bits = vec![true; 128];
+        // act/assert
+        assert_eq!(Ok(u128::MAX), binary_to_number::<u128>(&bits));
+    }
+
+    #[test]
+    fn binary_to_wide_number_never_overflows() {
+        // arrange
+        let mut bits = vec![true];
+        bits.extend([false; 100]);
+        // act
+        let result = binary_to_wide_number(&bits);
+        // assert
+        assert_eq!(101, result.bit_len());
+        assert_eq!(WideNumber::from(1u128 << 100), result);
+    }
+
+    #[test]
+    fn number_to_binary_round_trips_in_either_order() {
+        // arrange
+        let msb_first = vec![false, true, false, false, true];
+        // act
+        let result = number_to_binary(9u32, 5, BitOrder::MsbFirst).unwrap();
+        let lsb_first = number_to_binary(9u32, 5, BitOrder::LsbFirst).unwrap();
+        // assert
+        assert_eq!(msb_first, result);
+        assert_eq!(vec![true, false, false, true, false], lsb_first);
+        assert_eq!(Ok(9u8), binary_to_number_ordered(&lsb_first, BitOrder::LsbFirst));
+    }
+
+    #[test]
+    fn number_to_binary_pads_and_rejects_narrow_widths() {
+        assert_eq!(Ok(vec![false; 70]), number_to_binary(0u64, 70, BitOrder::MsbFirst));
+        assert_eq!(Err(BinaryOverflowError { significant_bits: 4, capacity: 3 }), number_to_binary(9u8, 3, BitOrder::MsbFirst));
+    }
+
+    #[test]
+    fn complement_number_stays_within_width() {
+        // arrange
+        let gamma = 22u32;
+        // act
+        let epsilon = complement_number(gamma, 5);
+        // assert
+        assert_eq!(9, epsilon);
+        assert_eq!(u128::MAX - 1, complement_number(1u128, 128));
+        assert_eq!(vec![true, false, true], complement(&[false, true, false]));
+    }
+
+    #[test]
+    fn gray_code_round_trips_and_matches_number_form() {
+        for n in 0u16..64 {
+            // arrange
+            let bits = number_to_binary(n, 6, BitOrder::MsbFirst).unwrap();
+            // act
+            let gray = gray_encode(&bits);
+            // assert
+            assert_eq!(Ok(gray_encode_number(n)), binary_to_number::<u16>(&gray));
+            assert_eq!(bits, gray_decode(&gray));
+            assert_eq!(n, gray_decode_number(gray_encode_number(n)));
+        }
+    }
+
+    #[test]
+    fn gray_codes_of_consecutive_numbers_differ_in_one_bit() {
+        // arrange
+        let three = gray_encode(&[false, true, true]);
+        let four = gray_encode(&[true, false, false]);
+        // act/assert
+        assert_eq!(Some(1), hamming_distance(&three, &four));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn hamming_weight_and_distance_count_bits() {
+        assert_eq!(3, hamming_weight(&[true, false, true, true]));
+        assert_eq!(Some(2), hamming_distance(&[true, false, true], &[false, false, false]));
+        assert_eq!(None, hamming_distance(&[true], &[true, false]));
+    }
+
+    #[test]
+    fn format_bits_pads_to_whole_digits() {
+        // arrange
+        let bits = vec![true, false, true, true, false, true];
+        // act/assert
+        assert_eq!("0b101101", format_bits(&bits, Radix::Binary));
+        assert_eq!("0o55", format_bits(&bits, Radix::Octal));
+        assert_eq!("0x2d", format_bits(&bits, Radix::Hex));
+    }
+
+    #[test]
+    fn parse_bits_reads_each_radix() {
+        assert_eq!(Ok(vec![false, false, true, false, true, true, false, true]), parse_bits("0x2d", None));
+        assert_eq!(Ok(vec![true, false, true, true, false, true]), parse_bits("0o55", None));
+        assert_eq!(Ok(vec![true, false, true, true, false, true]), parse_bits("0x2D", Some(6)));
+        assert_eq!(Ok(vec![false, true, true]), parse_bits("011", None));
+        assert_eq!(Ok(vec![false, false, false, true, true]), parse_bits("0b11", Some(5)));
+    }
+
+    #[test]
+    fn parse_bits_reports_bad_text() {
+        assert_eq!(Err(ParseBitsError::InvalidDigit('2')), parse_bits("0b102", None));
+        assert_eq!(Err(ParseBitsError::Empty), parse_bits("0x", None));
+        assert_eq!(Err(ParseBitsError::Overflow(BinaryOverflowError { significant_bits: 6, capacity: 5 })), parse_bits("0x2d", Some(5)));
+    }
+}
@@ -1,137 +1,377 @@
 use crate::grid::DynamicHeightGrid;
-use crate::grid::Transpose;
-use crate::binary::binary_to_number;
+use crate::packed::PackedBitGrid;
+use crate::parse::{parse_grid, parse_grid_collecting_errors, parse_row, BinaryDecoder, CellSplit, GridFormat, GridParseError, GridParseErrorKind};
+use crate::binary::{binary_to_wide_number, complement};
+use crate::wide::WideNumber;
+use std::cmp::Ordering;
+use std::fmt;
 
 /*
  * Types
  */
+// Rates have as many bits as the grid is wide, however wide that is
 pub struct DiagnosticResult {
-    pub gamma_rate: u32,
-    pub epsilon_rate: u32
+    pub gamma_rate: WideNumber,
+    pub epsilon_rate: WideNumber,
+    // Columns (counting from 0 on the left) with as many ones as zeros, settled by the tie policy
+    pub tie_columns: Vec<usize>
 }
 
+// How to settle a column with as many ones as zeros when finding the most common bit
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum TiePolicy {
+    #[default]
+    PreferOne,
+    PreferZero,
+    Error
+}
+
+// Raised under TiePolicy::Error, listing every tied column
+#[derive(PartialEq, Eq, Debug)]
+pub struct DiagnosticTieError {
+    pub tie_columns: Vec<usize>
+}
+
+impl fmt::Display for DiagnosticTieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns: Vec<String> = self.tie_columns.iter().map(|c| (c + 1).to_string()).collect();
+        write!(f, "columns {} were tied", columns.join(", "))
+    }
+}
+
+impl std::error::Error for DiagnosticTieError {}
+
 impl DiagnosticResult {
-    pub fn power_consumption(&self) -> u32 {
-        self.gamma_rate * self.epsilon_rate
+    pub fn power_consumption(&self) -> WideNumber {
+        self.gamma_rate.mul(&self.epsilon_rate)
     }
 }
 
+pub struct LifeSupportResult {
+    pub oxygen_generator_rating: WideNumber,
+    pub co2_scrubber_rating: WideNumber
+}
+
+impl LifeSupportResult {
+    pub fn life_support_rating(&self) -> WideNumber {
+        self.oxygen_generator_rating.mul(&self.co2_scrubber_rating)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum LifeSupportRating {
+    // Keeps rows with the most common bit, preferring 1 on a tie
+    OxygenGenerator,
+    // Keeps rows with the least common bit, preferring 0 on a tie
+    Co2Scrubber
+}
+
 #[derive(PartialEq, Eq, Debug)]
+pub enum LifeSupportError {
+    // When there are no rows to filter
+    EmptyGrid,
+    // When filtering leaves no rows. Second value is the bit position, counting from the left.
+    NoRowsRemaining(LifeSupportRating, usize),
+    // When every bit position has been used and identical rows remain. Second value is how many.
+    MultipleRowsRemaining(LifeSupportRating, usize)
+}
+
+impl fmt::Display for LifeSupportRating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LifeSupportRating::OxygenGenerator => write!(f, "oxygen generator rating"),
+            LifeSupportRating::Co2Scrubber => write!(f, "CO2 scrubber rating")
+        }
+    }
+}
+
+impl fmt::Display for LifeSupportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LifeSupportError::EmptyGrid => write!(f, "no diagnostic rows to filter"),
+            LifeSupportError::NoRowsRemaining(rating, position) => write!(f, "filtering for the {} left no rows at bit {}", rating, position + 1),
+            LifeSupportError::MultipleRowsRemaining(rating, count) => write!(f, "filtering for the {} left {} identical rows", rating, count)
+        }
+    }
+}
+
+impl std::error::Error for LifeSupportError {}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum DiagnosticParseErrorKind {
+    // A character other than 0 or 1. Value is the character.
+    InvalidCharacter(char),
+    // A line of a different length to the first line. Values are the expected and found lengths.
+    LineLengthMismatch(usize, usize)
+}
+
+// Line and column both count from 1. For a length mismatch the column is the first one past the shorter length.
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct DiagnosticParseError {
     pub line_number: usize,
+    pub column_number: usize,
     pub invalid_line: String,
-    pub why: String
-} 
+    pub kind: DiagnosticParseErrorKind
+}
+
+impl fmt::Display for DiagnosticParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticParseErrorKind::InvalidCharacter(c) => write!(f, "invalid boolean character '{}'", c),
+            DiagnosticParseErrorKind::LineLengthMismatch(expected, found) => write!(f, "line length {} mismatches first line length {}", found, expected)
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {} in \"{}\"", self.line_number, self.column_number, self.kind, self.invalid_line)
+    }
+}
+
+impl std::error::Error for DiagnosticParseError {}
+
+// The rows that parsed, along with every line that didn't
+pub struct CollectedDiagnosticGrid {
+    pub grid: DynamicHeightGrid<bool>,
+    pub errors: Vec<DiagnosticParseError>
+}
+
+// Raised when more lines fail to parse than the threshold allows, listing all of them
+#[derive(PartialEq, Eq, Debug)]
+pub struct TooManyParseErrors {
+    pub max_errors: usize,
+    pub errors: Vec<DiagnosticParseError>
+}
+
+impl fmt::Display for TooManyParseErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} lines failed to parse, more than the {} allowed", self.errors.len(), self.max_errors)
+    }
+}
+
+impl std::error::Error for TooManyParseErrors {}
 
 /*
  * Diagnostic logic
  */
-pub fn calculate_diagnostic_result(values: &DynamicHeightGrid<bool>) -> DiagnosticResult {
-    if values.len() == 0 {
+pub fn calculate_diagnostic_result(values: &DynamicHeightGrid<bool>, tie_policy: TiePolicy) -> Result<DiagnosticResult, DiagnosticTieError> {
+    if values.is_empty() {
         // We get nonsense if we're passed an empty grid,
         // so handle this case explicitly with result of 0
-        DiagnosticResult {
-            gamma_rate: 0,
-            epsilon_rate: 0
-        }
+        Ok(DiagnosticResult {
+            gamma_rate: WideNumber::default(),
+            epsilon_rate: WideNumber::default(),
+            tie_columns: Vec::new()
+        })
     } else {
-        let values_transposed = values.transpose();
-        let collapsed_gamma: Vec<bool> = values_transposed
-            .iter()
-            .map(most_common_bool)
+        let majorities: Vec<Option<bool>> = values
+            .view()
+            .transpose()
+            .rows()
+            .map(|column| majority_from_count(column.filter(|b| **b).count(), values.len()))
             .collect();
+        diagnostic_result_from_majorities(&majorities, tie_policy)
+    }
+}
 
-        let collapsed_epsilon: Vec<bool> = collapsed_gamma
-            .iter()
-            .map(|b| !b)
+// Gives the same result as calculate_diagnostic_result, counting each column's ones straight from the packed rows
+pub fn calculate_packed_diagnostic_result(values: &PackedBitGrid, tie_policy: TiePolicy) -> Result<DiagnosticResult, DiagnosticTieError> {
+    if values.is_empty() {
+        Ok(DiagnosticResult {
+            gamma_rate: WideNumber::default(),
+            epsilon_rate: WideNumber::default(),
+            tie_columns: Vec::new()
+        })
+    } else {
+        let majorities: Vec<Option<bool>> = values
+            .column_counts()
+            .into_iter()
+            .map(|ones| majority_from_count(ones, values.len()))
             .collect();
+        diagnostic_result_from_majorities(&majorities, tie_policy)
+    }
+}
 
-        DiagnosticResult {
-            gamma_rate: binary_to_number(&collapsed_gamma),
-            epsilon_rate: binary_to_number(&collapsed_epsilon)
+// Builds the rates from each column's most common bit, settling ties by the policy
+fn diagnostic_result_from_majorities(majorities: &[Option<bool>], tie_policy: TiePolicy) -> Result<DiagnosticResult, DiagnosticTieError> {
+    let mut tie_columns = Vec::new();
+    let collapsed_gamma: Vec<bool> = majorities
+        .iter()
+        .enumerate()
+        .map(|(column, majority)| majority.unwrap_or_else(|| {
+            tie_columns.push(column);
+            tie_policy == TiePolicy::PreferOne
+        }))
+        .collect();
+
+    if tie_policy == TiePolicy::Error && !tie_columns.is_empty() {
+        return Err(DiagnosticTieError { tie_columns });
+    }
+
+    Ok(DiagnosticResult {
+        gamma_rate: binary_to_wide_number(&collapsed_gamma),
+        epsilon_rate: binary_to_wide_number(&complement(&collapsed_gamma)),
+        tie_columns
+    })
+}
+
+pub fn calculate_life_support_result(values: &DynamicHeightGrid<bool>) -> Result<LifeSupportResult, LifeSupportError> {
+    Ok(LifeSupportResult {
+        oxygen_generator_rating: filter_to_rating(values, LifeSupportRating::OxygenGenerator)?,
+        co2_scrubber_rating: filter_to_rating(values, LifeSupportRating::Co2Scrubber)?
+    })
+}
+
+// Repeatedly keeps only the rows matching the rating's bit criteria, one position at a time from the left,
+// until a single row remains. When the remaining rows all share a bit, the least common bit appears in none of
+// them, so the CO2 scrubber filter can run out of rows.
+fn filter_to_rating(values: &DynamicHeightGrid<bool>, rating: LifeSupportRating) -> Result<WideNumber, LifeSupportError> {
+    let mut remaining: Vec<&Vec<bool>> = values.iter().collect();
+    if remaining.is_empty() {
+        return Err(LifeSupportError::EmptyGrid);
+    }
+
+    for position in 0..values.width {
+        if remaining.len() == 1 {
+            break;
+        }
+        let column: Vec<bool> = remaining.iter().map(|row| row[position]).collect();
+        let most_common = most_common_bool(&column);
+        let wanted = match rating {
+            LifeSupportRating::OxygenGenerator => most_common.unwrap_or(true),
+            LifeSupportRating::Co2Scrubber => most_common.is_some_and(|b| !b)
+        };
+        remaining.retain(|row| row[position] == wanted);
+        if remaining.is_empty() {
+            return Err(LifeSupportError::NoRowsRemaining(rating, position));
         }
     }
+
+    match remaining[..] {
+        [row] => Ok(binary_to_wide_number(row)),
+        _ => Err(LifeSupportError::MultipleRowsRemaining(rating, remaining.len()))
+    }
 }
 
 /*
  * Diagnostic parsing
  */
-pub fn diagnostic_grid_from_lines(lines: &Vec<String>) -> Result<DynamicHeightGrid<bool>, DiagnosticParseError> {
-    if lines.len() == 0 {
-        return Ok(DynamicHeightGrid::new(0))
+pub fn diagnostic_grid_from_lines(lines: &[String]) -> Result<DynamicHeightGrid<bool>, DiagnosticParseError> {
+    parse_grid(lines, &BinaryDecoder, &GridFormat::default()).map_err(|e| diagnostic_parse_error(&lines[e.row], e))
+}
+
+// Keeps going past bad lines, leaving them out of the grid, and only fails when more than max_errors lines are
+// bad. The grid takes the width of the first line that parses.
+pub fn diagnostic_grid_collecting_errors(lines: &[String], max_errors: usize) -> Result<CollectedDiagnosticGrid, TooManyParseErrors> {
+    let (grid, grid_errors) = parse_grid_collecting_errors(lines, &BinaryDecoder, &GridFormat::default());
+    let errors: Vec<DiagnosticParseError> = grid_errors
+        .into_iter()
+        .map(|e| diagnostic_parse_error(&lines[e.row], e))
+        .collect();
+
+    if errors.len() > max_errors {
+        return Err(TooManyParseErrors { max_errors, errors });
     }
+    Ok(CollectedDiagnosticGrid { grid, errors })
+}
 
-    let mut grid = DynamicHeightGrid::new(lines[0].len());
+// Parses straight into packed rows, so large diagnostics never hold a byte per bit
+pub fn packed_diagnostic_grid_from_lines(lines: &[String]) -> Result<PackedBitGrid, DiagnosticParseError> {
+    let mut grid = PackedBitGrid::new(lines.first().map_or(0, |line| line.chars().count()));
 
     for (i, line) in lines.iter().enumerate() {
-        let bool_line_with_errs: Vec<Result<bool, DiagnosticParseError>> = line
-            .chars()
-            .map(|c| char_to_bool(c).ok_or(DiagnosticParseError {
-                line_number: i + 1,
-                invalid_line: line.to_string(),
-                why: "Invalid boolean character".to_string()
-            }))
-            .collect();
-        
-        let bool_line: Vec<bool> = bool_line_with_errs
-            .into_iter()
-            .collect::<Result<Vec<bool>, DiagnosticParseError>>()?;
-
-        grid.push(bool_line).map_err(|_| DiagnosticParseError {
-            line_number: i + 1,
-            invalid_line: line.to_string(),
-            why: "Line length mismatches first line".to_string()
-        })?;
+        let bool_line = parse_diagnostic_line(i, line)?;
+        grid.push(&bool_line).map_err(|_| line_length_error(i, line, grid.width, bool_line.len()))?;
     }
 
-    return Ok(grid);
+    Ok(grid)
 }
 
 /*
  * Helper functions
  */
-fn most_common_bool(booleans: &Vec<bool>) -> bool {
-    let l = booleans.len();
+fn parse_diagnostic_line(i: usize, line: &str) -> Result<Vec<bool>, DiagnosticParseError> {
+    parse_row(i, line, &BinaryDecoder, CellSplit::Chars).map_err(|e| diagnostic_parse_error(line, e))
+}
+
+// Keeps the report's own wording for errors from the generic grid parser
+fn diagnostic_parse_error(line: &str, e: GridParseError) -> DiagnosticParseError {
+    match e.kind {
+        GridParseErrorKind::RaggedRow(expected, found) => line_length_error(e.row, line, expected, found),
+        GridParseErrorKind::InvalidChar(c) => DiagnosticParseError {
+            line_number: e.row + 1,
+            column_number: e.column + 1,
+            invalid_line: line.to_string(),
+            kind: DiagnosticParseErrorKind::InvalidCharacter(c)
+        },
+        // Only other splits give whole tokens, so report the first character of an empty or unknown token
+        GridParseErrorKind::InvalidToken(token) => DiagnosticParseError {
+            line_number: e.row + 1,
+            column_number: e.column + 1,
+            invalid_line: line.to_string(),
+            kind: DiagnosticParseErrorKind::InvalidCharacter(token.chars().next().unwrap_or(char::REPLACEMENT_CHARACTER))
+        }
+    }
+}
+
+fn line_length_error(i: usize, line: &str, expected: usize, found: usize) -> DiagnosticParseError {
+    DiagnosticParseError {
+        line_number: i + 1,
+        column_number: expected.min(found) + 1,
+        invalid_line: line.to_string(),
+        kind: DiagnosticParseErrorKind::LineLengthMismatch(expected, found)
+    }
+}
+
+// The strict majority value, or None when there are as many trues as falses
+fn most_common_bool(booleans: &[bool]) -> Option<bool> {
     let l_true = booleans
         .iter()
         .filter(|b| **b)
         .count();
 
-    l_true >= l / 2
+    majority_from_count(l_true, booleans.len())
 }
 
-fn char_to_bool(c: char) -> Option<bool> {
-    match c {
-        '0' => {
-            Some(false)
-        },
-        '1' => {
-            Some(true)
-        },
-        _ => {
-            None
-        }
+// The strict majority value given how many of l values are true
+fn majority_from_count(l_true: usize, l: usize) -> Option<bool> {
+    match (l_true * 2).cmp(&l) {
+        Ordering::Greater => Some(true),
+        Ordering::Less => Some(false),
+        Ordering::Equal => None
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::DiagnosticResult;
+    use super::DiagnosticTieError;
+    use super::TiePolicy;
+    use super::LifeSupportError;
+    use super::LifeSupportRating;
     use super::calculate_diagnostic_result;
+    use super::calculate_packed_diagnostic_result;
+    use super::packed_diagnostic_grid_from_lines;
+    use super::calculate_life_support_result;
     use super::diagnostic_grid_from_lines;
+    use super::diagnostic_grid_collecting_errors;
+    use super::DiagnosticParseErrorKind;
     use crate::grid::DynamicHeightGrid;
+    use crate::wide::WideNumber;
 
     #[test]
     fn diagnostic_result_power_consumption_multiplies() {
         // arrange
         let diag = DiagnosticResult {
-            gamma_rate: 3,
-            epsilon_rate: 4
+            gamma_rate: WideNumber::from(3u64),
+            epsilon_rate: WideNumber::from(4u64),
+            tie_columns: Vec::new()
         };
         // act
         let result = diag.power_consumption();
         // assert
-        assert_eq!(12, result);
+        assert_eq!(WideNumber::from(12u64), result);
     }
 
     // This test verifies behaviour against the advent of code example
@@ -152,20 +392,139 @@ mod tests {
         grid.push(vec![false, false, false, true, false]).unwrap();
         grid.push(vec![false, true, false, true, false]).unwrap();
         // act
-        let result = calculate_diagnostic_result(&grid);
+        let result = calculate_diagnostic_result(&grid, TiePolicy::PreferOne).unwrap();
+        // assert
+        assert_eq!(WideNumber::from(22u64), result.gamma_rate);
+        assert_eq!(WideNumber::from(9u64), result.epsilon_rate);
+        assert_eq!(WideNumber::from(198u64), result.power_consumption());
+        assert!(result.tie_columns.is_empty());
+    }
+
+    #[test]
+    fn calculate_diagnostic_result_uses_exact_majority_for_odd_lengths() {
+        // arrange
+        // Two ones out of five is a minority, in both columns
+        let data: Vec<String> = ["10", "01", "00", "11", "00"].iter().map(|s| s.to_string()).collect();
+        let grid = diagnostic_grid_from_lines(&data).unwrap();
+        // act
+        let result = calculate_diagnostic_result(&grid, TiePolicy::PreferOne).unwrap();
         // assert
-        assert_eq!(22, result.gamma_rate);
-        assert_eq!(9, result.epsilon_rate);
-        assert_eq!(198, result.power_consumption());
+        assert_eq!(WideNumber::from(0u64), result.gamma_rate);
+        assert_eq!(WideNumber::from(3u64), result.epsilon_rate);
+    }
+
+    #[test]
+    fn calculate_diagnostic_result_handles_100_bit_rows() {
+        // arrange
+        // Gamma is a single one followed by 99 zeros, so epsilon is the 99 ones beneath it
+        let mut majority_row = "1".to_string();
+        majority_row.push_str(&"0".repeat(99));
+        let data = vec![majority_row.clone(), majority_row, "0".repeat(100)];
+        let grid = diagnostic_grid_from_lines(&data).unwrap();
+        let packed = packed_diagnostic_grid_from_lines(&data).unwrap();
+        // act
+        let result = calculate_diagnostic_result(&grid, TiePolicy::PreferOne).unwrap();
+        let packed_result = calculate_packed_diagnostic_result(&packed, TiePolicy::PreferOne).unwrap();
+        // assert
+        assert_eq!("633825300114114700748351602688", result.gamma_rate.to_string());
+        assert_eq!("633825300114114700748351602687", result.epsilon_rate.to_string());
+        assert_eq!(198, result.power_consumption().bit_len());
+        assert_eq!(result.power_consumption(), packed_result.power_consumption());
+    }
+
+    #[test]
+    fn calculate_packed_diagnostic_result_matches_unpacked() {
+        // arrange
+        let data: Vec<String> = ["00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000", "11001", "00010", "01010"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let grid = diagnostic_grid_from_lines(&data).unwrap();
+        let packed = packed_diagnostic_grid_from_lines(&data).unwrap();
+        for tie_policy in [TiePolicy::PreferOne, TiePolicy::PreferZero] {
+            // act
+            let expected = calculate_diagnostic_result(&grid, tie_policy).unwrap();
+            let result = calculate_packed_diagnostic_result(&packed, tie_policy).unwrap();
+            // assert
+            assert_eq!(expected.gamma_rate, result.gamma_rate);
+            assert_eq!(expected.epsilon_rate, result.epsilon_rate);
+            assert_eq!(expected.tie_columns, result.tie_columns);
+        }
+    }
+
+    #[test]
+    fn calculate_diagnostic_result_settles_ties_by_policy() {
+        // arrange
+        let data: Vec<String> = ["100", "001", "101", "000"].iter().map(|s| s.to_string()).collect();
+        let grid = diagnostic_grid_from_lines(&data).unwrap();
+        // act
+        let prefer_one = calculate_diagnostic_result(&grid, TiePolicy::PreferOne).unwrap();
+        let prefer_zero = calculate_diagnostic_result(&grid, TiePolicy::PreferZero).unwrap();
+        let error = calculate_diagnostic_result(&grid, TiePolicy::Error);
+        // assert
+        assert_eq!(WideNumber::from(5u64), prefer_one.gamma_rate);
+        assert_eq!(vec![0, 2], prefer_one.tie_columns);
+        assert_eq!(WideNumber::from(0u64), prefer_zero.gamma_rate);
+        assert_eq!(WideNumber::from(7u64), prefer_zero.epsilon_rate);
+        assert_eq!(vec![0, 2], prefer_zero.tie_columns);
+        match error {
+            Ok(_) => panic!("Expected failure"),
+            Err(e) => {
+                assert_eq!("columns 1, 3 were tied", e.to_string());
+                assert_eq!(DiagnosticTieError { tie_columns: vec![0, 2] }, e)
+            }
+        }
+    }
+
+    // This test verifies behaviour against the advent of code example
+    #[test]
+    fn calculate_life_support_result_matches_example() {
+        // arrange
+        let data: Vec<String> = ["00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000", "11001", "00010", "01010"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let grid = diagnostic_grid_from_lines(&data).unwrap();
+        // act
+        let result = calculate_life_support_result(&grid).unwrap();
+        // assert
+        assert_eq!(WideNumber::from(23u64), result.oxygen_generator_rating);
+        assert_eq!(WideNumber::from(10u64), result.co2_scrubber_rating);
+        assert_eq!(WideNumber::from(230u64), result.life_support_rating());
+    }
+
+    #[test]
+    fn calculate_life_support_result_runs_out_of_rows() {
+        // arrange
+        let data = vec!["110".to_string(), "111".to_string()];
+        let grid = diagnostic_grid_from_lines(&data).unwrap();
+        // act
+        let result = calculate_life_support_result(&grid);
+        // assert
+        match result {
+            Ok(_) => panic!("Expected failure"),
+            Err(e) => assert_eq!(LifeSupportError::NoRowsRemaining(LifeSupportRating::Co2Scrubber, 0), e)
+        }
+    }
+
+    #[test]
+    fn calculate_life_support_result_of_empty_grid_fails() {
+        // arrange/act
+        let result = calculate_life_support_result(&DynamicHeightGrid::<bool>::new(5));
+        // assert
+        match result {
+            Ok(_) => panic!("Expected failure"),
+            Err(e) => assert_eq!(LifeSupportError::EmptyGrid, e)
+        }
     }
 
     #[test]
     fn calculate_empty_grid_is_zero() {
         // arrange/act
-        let result = calculate_diagnostic_result(&DynamicHeightGrid::<bool>::new(5));
+        let result = calculate_diagnostic_result(&DynamicHeightGrid::<bool>::new(5), TiePolicy::Error).unwrap();
         // assert
-        assert_eq!(0, result.gamma_rate);
-        assert_eq!(0, result.epsilon_rate);
+        assert_eq!(WideNumber::from(0u64), result.gamma_rate);
+        assert_eq!(WideNumber::from(0u64), result.epsilon_rate);
     }
 
     #[test]
@@ -203,8 +562,9 @@ mod tests {
             },
             Err(e) => {
                 assert_eq!(2, e.line_number);
+                assert_eq!(4, e.column_number);
                 assert_eq!("111".to_string(), e.invalid_line);
-                assert_eq!("Line length mismatches first line".to_string(), e.why);
+                assert_eq!(DiagnosticParseErrorKind::LineLengthMismatch(4, 3), e.kind);
             }
         }
     }
@@ -222,8 +582,62 @@ mod tests {
             },
             Err(e) => {
                 assert_eq!(1, e.line_number);
+                assert_eq!(4, e.column_number);
                 assert_eq!("0102".to_string(), e.invalid_line);
-                assert_eq!("Invalid boolean character".to_string(), e.why);
+                assert_eq!(DiagnosticParseErrorKind::InvalidCharacter('2'), e.kind);
+                assert_eq!("line 1, column 4: invalid boolean character '2' in \"0102\"", e.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn packed_diagnostic_grid_from_invalid_char_reports_its_line() {
+        // arrange
+        let data = vec!["0101".to_string(), "0121".to_string()];
+        // act
+        let result = packed_diagnostic_grid_from_lines(&data);
+        // assert
+        match result {
+            Ok(_) => {
+                panic!("Expected failure");
+            },
+            Err(e) => {
+                assert_eq!(2, e.line_number);
+                assert_eq!(3, e.column_number);
+                assert_eq!("0121".to_string(), e.invalid_line);
+                assert_eq!(DiagnosticParseErrorKind::InvalidCharacter('2'), e.kind);
+            }
+        }
+    }
+
+    #[test]
+    fn diagnostic_grid_collecting_errors_keeps_valid_rows() {
+        // arrange
+        let data: Vec<String> = ["0102", "0101", "11", "1111", "x000"].iter().map(|s| s.to_string()).collect();
+        // act
+        let result = diagnostic_grid_collecting_errors(&data, 3).unwrap();
+        // assert
+        assert_eq!(2, result.grid.len());
+        assert_eq!(vec![true, true, true, true], result.grid[1]);
+        let locations: Vec<(usize, usize)> = result.errors.iter().map(|e| (e.line_number, e.column_number)).collect();
+        assert_eq!(vec![(1, 4), (3, 3), (5, 1)], locations);
+        assert_eq!(DiagnosticParseErrorKind::LineLengthMismatch(4, 2), result.errors[1].kind);
+    }
+
+    #[test]
+    fn diagnostic_grid_collecting_errors_fails_above_threshold() {
+        // arrange
+        let data: Vec<String> = ["0101", "0121", "0a01"].iter().map(|s| s.to_string()).collect();
+        // act
+        let result = diagnostic_grid_collecting_errors(&data, 1);
+        // assert
+        match result {
+            Ok(_) => {
+                panic!("Expected failure");
+            },
+            Err(e) => {
+                assert_eq!(2, e.errors.len());
+                assert_eq!("2 lines failed to parse, more than the 1 allowed", e.to_string());
             }
         }
     }
@@ -1,4 +1,5 @@
-use std::ops::Index;
+use crate::view::GridView;
+use std::ops::{Index, IndexMut, Range};
 
 pub trait Transpose {
     fn transpose(&self) -> Self;
@@ -13,7 +14,7 @@ pub struct DynamicHeightGrid<T> {
 impl<T> DynamicHeightGrid<T> {
     pub fn new(width: usize) -> Self {
         Self {
-            width: width,
+            width,
             data_rows: Default::default()
         }
     }
@@ -31,35 +32,193 @@ impl<T> DynamicHeightGrid<T> {
         self.data_rows.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.data_rows.is_empty()
+    }
+
     pub fn iter(&self) -> std::slice::Iter<'_, Vec<T>> {
         self.data_rows.iter()
     }
-}
 
-impl<T: Copy + Default> Transpose for DynamicHeightGrid<T> {
-    fn transpose(&self) -> Self {
+    // Rows are handed out as slices so they can't change length
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [T]> {
+        self.data_rows.iter_mut().map(|row| row.as_mut_slice())
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Option<&T> {
+        self.data_rows.get(row).and_then(|r| r.get(column))
+    }
+
+    pub fn get_mut(&mut self, row: usize, column: usize) -> Option<&mut T> {
+        self.data_rows.get_mut(row).and_then(|r| r.get_mut(column))
+    }
+
+    // Cells of one column from the top row down
+    pub fn column(&self, column: usize) -> impl Iterator<Item = &T> {
+        assert!(column < self.width, "Column {} is outside a grid of width {}", column, self.width);
+        self.data_rows.iter().map(move |row| &row[column])
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(move |column| self.column(column))
+    }
+
+    // Every cell with its (row, column) coordinates, row by row
+    pub fn enumerate(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.data_rows
+            .iter()
+            .enumerate()
+            .flat_map(|(r, row)| row.iter().enumerate().map(move |(c, cell)| ((r, c), cell)))
+    }
+
+    pub fn map<U, F: FnMut(&T) -> U>(&self, mut f: F) -> DynamicHeightGrid<U> {
+        DynamicHeightGrid {
+            width: self.width,
+            data_rows: self.data_rows.iter().map(|row| row.iter().map(&mut f).collect()).collect()
+        }
+    }
+
+    pub fn insert(&mut self, index: usize, row: Vec<T>) -> Result<(), String> {
+        if row.len() != self.width {
+            Err(format!("This grid supports rows of length {}, but got a row of length {}", self.width, row.len()))
+        } else if index > self.data_rows.len() {
+            Err(format!("Can't insert a row at {} in a grid of {} rows", index, self.data_rows.len()))
+        } else {
+            self.data_rows.insert(index, row);
+            Ok(())
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Vec<T>> {
+        if index < self.data_rows.len() {
+            Some(self.data_rows.remove(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn retain<F: FnMut(&[T]) -> bool>(&mut self, mut f: F) {
+        self.data_rows.retain(|row| f(row));
+    }
+
+    // A transposed, rotated or flipped view starts from this
+    pub fn view(&self) -> GridView<'_, T> {
+        GridView::new(self)
+    }
+
+    // Transposes by moving the cells, so they needn't be Clone
+    pub fn into_transposed(self) -> Self {
         let height = self.data_rows.len();
-        let mut new_vec = vec![vec![Default::default(); height]; self.width];
+        let mut columns: Vec<Vec<T>> = (0..self.width).map(|_| Vec::with_capacity(height)).collect();
+        for row in self.data_rows {
+            for (column, cell) in columns.iter_mut().zip(row) {
+                column.push(cell);
+            }
+        }
+        Self {
+            width: height,
+            data_rows: columns
+        }
+    }
 
-        for (i, row) in self.data_rows.iter().enumerate() {
-            for (j, &elem) in row.iter().enumerate() {
-                new_vec[j][i] = elem.clone();
+    // Transposes a square grid by swapping cells across the diagonal, without allocating
+    pub fn transpose_in_place(&mut self) -> Result<(), String> {
+        if self.data_rows.len() != self.width {
+            return Err(format!("Only a square grid can be transposed in place, but this grid is {} by {}", self.data_rows.len(), self.width));
+        }
+        for i in 0..self.width {
+            let (upper, lower) = self.data_rows.split_at_mut(i + 1);
+            for (offset, row) in lower.iter_mut().enumerate() {
+                std::mem::swap(&mut upper[i][i + 1 + offset], &mut row[i]);
             }
         }
+        Ok(())
+    }
+}
 
+impl<T: Clone> DynamicHeightGrid<T> {
+    pub fn slice_rows(&self, rows: Range<usize>) -> Self {
         Self {
-            width: height,
-            data_rows: new_vec
+            width: self.width,
+            data_rows: self.data_rows[rows].to_vec()
+        }
+    }
+
+    pub fn slice_columns(&self, columns: Range<usize>) -> Self {
+        assert!(columns.end <= self.width, "Columns {:?} are outside a grid of width {}", columns, self.width);
+        Self {
+            width: columns.len(),
+            data_rows: self.data_rows.iter().map(|row| row[columns.clone()].to_vec()).collect()
+        }
+    }
+}
+
+impl<T: Clone> Transpose for DynamicHeightGrid<T> {
+    fn transpose(&self) -> Self {
+        Self {
+            width: self.data_rows.len(),
+            data_rows: (0..self.width).map(|j| self.column(j).cloned().collect()).collect()
         }
- 
     }
 }
 
 impl<T> Index<usize> for DynamicHeightGrid<T> {
-    type Output = Vec<T>;
+    type Output = [T];
 
     fn index(&self, index: usize) -> &Self::Output {
-        return &self.data_rows[index];
+        &self.data_rows[index]
+    }
+}
+
+impl<T> IndexMut<usize> for DynamicHeightGrid<T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.data_rows[index]
+    }
+}
+
+// Indexes a single cell by (row, column)
+impl<T> Index<(usize, usize)> for DynamicHeightGrid<T> {
+    type Output = T;
+
+    fn index(&self, (row, column): (usize, usize)) -> &Self::Output {
+        &self.data_rows[row][column]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for DynamicHeightGrid<T> {
+    fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut Self::Output {
+        &mut self.data_rows[row][column]
+    }
+}
+
+// The width is taken from the first row, or zero when there are none. Panics on a row of a different width, so
+// use push for rows that might not fit.
+impl<T> FromIterator<Vec<T>> for DynamicHeightGrid<T> {
+    fn from_iter<I: IntoIterator<Item = Vec<T>>>(iter: I) -> Self {
+        let mut rows = iter.into_iter().peekable();
+        let mut grid = Self::new(rows.peek().map_or(0, |row| row.len()));
+        for row in rows {
+            grid.push(row).expect("All rows collected into a grid must have the same width");
+        }
+        grid
+    }
+}
+
+impl<T> IntoIterator for DynamicHeightGrid<T> {
+    type Item = Vec<T>;
+    type IntoIter = std::vec::IntoIter<Vec<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data_rows.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a DynamicHeightGrid<T> {
+    type Item = &'a Vec<T>;
+    type IntoIter = std::slice::Iter<'a, Vec<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data_rows.iter()
     }
 }
 
@@ -128,6 +287,46 @@ mod tests {
         assert_eq!(transposed_grid[2], vec![-5, 9]);
     }
 
+    #[test]
+    fn dynamic_height_grid_transpose_works_for_clone_and_owned_cells() {
+        // arrange
+        let grid: DynamicHeightGrid<String> = vec![
+            vec!["a".to_string(), "b".to_string()],
+            vec!["c".to_string(), "d".to_string()],
+            vec!["e".to_string(), "f".to_string()]
+        ].into_iter().collect();
+        // act
+        let cloned = grid.transpose();
+        let moved = grid.into_transposed();
+        // assert
+        assert_eq!(cloned, moved);
+        assert_eq!(3, moved.width);
+        assert_eq!(vec!["a", "c", "e"], moved[0]);
+        assert_eq!(vec!["b", "d", "f"], moved[1]);
+    }
+
+    #[test]
+    fn dynamic_height_grid_transpose_in_place_matches_transpose() {
+        // arrange
+        let mut grid = sample_grid();
+        let expected = grid.transpose();
+        // act
+        let result = grid.transpose_in_place();
+        // assert
+        assert_eq!(Ok(()), result);
+        assert_eq!(expected, grid);
+    }
+
+    #[test]
+    fn dynamic_height_grid_transpose_in_place_needs_square_grid() {
+        // arrange
+        let mut grid = sample_grid().slice_rows(0..2);
+        // act
+        let result = grid.transpose_in_place();
+        // assert
+        assert_eq!(Err("Only a square grid can be transposed in place, but this grid is 2 by 3".to_string()), result);
+    }
+
     #[test]
     fn grid_can_be_iterated() {
         // arrange
@@ -140,4 +339,109 @@ mod tests {
         // assert
         assert_eq!(result, vec![3, 7]);
     }
-}
\ No newline at end of file
+
+    fn sample_grid() -> DynamicHeightGrid<i32> {
+        vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]].into_iter().collect()
+    }
+
+    #[test]
+    fn grid_cells_can_be_read_and_written() {
+        // arrange
+        let mut grid = sample_grid();
+        // act
+        *grid.get_mut(0, 1).unwrap() = 20;
+        grid[(2, 2)] = 90;
+        grid[1][0] = 40;
+        // assert
+        assert_eq!(Some(&20), grid.get(0, 1));
+        assert_eq!(90, grid[(2, 2)]);
+        assert_eq!(vec![40, 5, 6], grid[1]);
+        assert_eq!(None, grid.get(0, 3));
+        assert_eq!(None, grid.get(3, 0));
+    }
+
+    #[test]
+    fn grid_columns_run_top_to_bottom() {
+        // arrange
+        let grid = sample_grid();
+        // act
+        let column: Vec<i32> = grid.column(1).copied().collect();
+        let sums: Vec<i32> = grid.columns().map(|c| c.sum()).collect();
+        // assert
+        assert_eq!(vec![2, 5, 8], column);
+        assert_eq!(vec![12, 15, 18], sums);
+    }
+
+    #[test]
+    fn grid_slices_rows_and_columns() {
+        // arrange
+        let grid = sample_grid();
+        // act
+        let rows = grid.slice_rows(1..3);
+        let columns = grid.slice_columns(0..2);
+        // assert
+        assert_eq!(2, rows.len());
+        assert_eq!(vec![4, 5, 6], rows[0]);
+        assert_eq!(2, columns.width);
+        assert_eq!(vec![7, 8], columns[2]);
+    }
+
+    #[test]
+    fn grid_map_keeps_shape() {
+        // arrange
+        let grid = sample_grid();
+        // act
+        let result = grid.map(|&n| n % 2 == 0);
+        // assert
+        assert_eq!(3, result.width);
+        assert_eq!(vec![false, true, false], result[0]);
+        assert_eq!(vec![true, false, true], result[1]);
+    }
+
+    #[test]
+    fn grid_rows_can_be_inserted_removed_and_retained() {
+        // arrange
+        let mut grid = sample_grid();
+        // act
+        let inserted = grid.insert(1, vec![0, 0, 0]);
+        let mismatched = grid.insert(0, vec![0]);
+        let removed = grid.remove(0);
+        grid.retain(|row| row[0] != 7);
+        // assert
+        assert_eq!(Ok(()), inserted);
+        assert_eq!(Err("This grid supports rows of length 3, but got a row of length 1".to_string()), mismatched);
+        assert_eq!(Some(vec![1, 2, 3]), removed);
+        assert_eq!(vec![vec![0, 0, 0], vec![4, 5, 6]], grid.into_iter().collect::<Vec<Vec<i32>>>());
+    }
+
+    #[test]
+    fn grid_enumerate_yields_coordinates() {
+        // arrange
+        let grid = sample_grid();
+        // act
+        let result: Vec<(usize, usize)> = grid.enumerate().filter(|(_, &n)| n % 4 == 0).map(|(at, _)| at).collect();
+        // assert
+        assert_eq!(vec![(1, 0), (2, 1)], result);
+        assert_eq!(9, grid.enumerate().count());
+    }
+
+    #[test]
+    fn grid_collects_from_rows() {
+        // arrange
+        let rows: Vec<Vec<i32>> = Vec::new();
+        // act
+        let empty: DynamicHeightGrid<i32> = rows.into_iter().collect();
+        let grid = sample_grid();
+        // assert
+        assert!(empty.is_empty());
+        assert_eq!(0, empty.width);
+        assert_eq!(3, grid.width);
+        assert_eq!(3, (&grid).into_iter().count());
+    }
+
+    #[test]
+    #[should_panic(expected = "All rows collected into a grid must have the same width")]
+    fn grid_collect_of_ragged_rows_panics() {
+        let _: DynamicHeightGrid<i32> = vec![vec![1, 2], vec![3]].into_iter().collect();
+    }
+}
@@ -1,4 +1,9 @@
 pub mod grid;
+pub mod view;
+pub mod parse;
+pub mod search;
 pub mod diagnostic;
 pub mod binary;
+pub mod packed;
+pub mod wide;
 pub mod file;
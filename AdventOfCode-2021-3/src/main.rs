@@ -1,6 +1,7 @@
-use clap::{arg, command};
+use clap::{arg, command, value_parser, ArgAction};
 use advent_of_code_2021_3::file::load_string_txt_to_vector;
-use advent_of_code_2021_3::diagnostic::{calculate_diagnostic_result, diagnostic_grid_from_lines};
+use advent_of_code_2021_3::grid::DynamicHeightGrid;
+use advent_of_code_2021_3::diagnostic::{calculate_diagnostic_result, calculate_life_support_result, calculate_packed_diagnostic_result, diagnostic_grid_collecting_errors, diagnostic_grid_from_lines, packed_diagnostic_grid_from_lines, DiagnosticParseError, DiagnosticResult, DiagnosticTieError, TiePolicy};
 
 fn main() {
     let matches = command!()
@@ -9,31 +10,121 @@ fn main() {
                 .required(true)
                 .index(1)
         )
+        .arg(
+            arg!(--ties <POLICY> "How to settle columns with as many ones as zeros: one, zero or error")
+                .value_parser(["one", "zero", "error"])
+                .default_value("one")
+        )
+        .arg(
+            arg!(--packed "Store the diagnostic bit-packed, for very large inputs. Only power consumption is reported.")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            arg!(--"max-errors" <N> "Skip bad lines and report them all, failing only if there are more than N")
+                .value_parser(value_parser!(usize))
+                .conflicts_with("packed")
+        )
         .get_matches();
 
     let filename = matches
         .get_one::<String>("filename")
         .expect("Expecting an input file");
 
-    match load_string_txt_to_vector(&filename) {
+    let tie_policy = match matches.get_one::<String>("ties").map(|policy| policy.as_str()) {
+        Some("zero") => TiePolicy::PreferZero,
+        Some("error") => TiePolicy::Error,
+        _ => TiePolicy::PreferOne
+    };
+
+    match load_string_txt_to_vector(filename) {
         Ok(lines) => {
-            process_input_lines(lines);
+            if matches.get_flag("packed") {
+                process_input_lines_packed(lines, tie_policy);
+            } else if let Some(&max_errors) = matches.get_one::<usize>("max-errors") {
+                process_input_lines_collecting_errors(lines, tie_policy, max_errors);
+            } else {
+                process_input_lines(lines, tie_policy);
+            }
         },
         Err(e) => {
-            println!("Failed to load file: {}", e.to_string());
+            println!("Failed to load file: {}", e);
         }
     }
 }
 
-fn process_input_lines(lines: Vec<String>) {
+fn process_input_lines(lines: Vec<String>, tie_policy: TiePolicy) {
     match diagnostic_grid_from_lines(&lines) {
         Ok(grid) => {
-            let diagnostic_result = calculate_diagnostic_result(&grid);
+            report_ratings(&grid, tie_policy);
+        },
+        Err(e) => {
+            report_parse_error(&e);
+        }
+    }
+
+}
+
+fn process_input_lines_collecting_errors(lines: Vec<String>, tie_policy: TiePolicy, max_errors: usize) {
+    match diagnostic_grid_collecting_errors(&lines, max_errors) {
+        Ok(collected) => {
+            for e in &collected.errors {
+                println!("Skipped {}", e);
+            }
+            report_ratings(&collected.grid, tie_policy);
+        },
+        Err(e) => {
+            for parse_error in &e.errors {
+                println!("Bad {}", parse_error);
+            }
+            println!("Parse of diagnostic grid failed: {}", e);
+        }
+    }
+}
+
+fn report_ratings(grid: &DynamicHeightGrid<bool>, tie_policy: TiePolicy) {
+    report_power_consumption(calculate_diagnostic_result(grid, tie_policy));
+    match calculate_life_support_result(grid) {
+        Ok(life_support) => {
+            println!("Oxygen generator rating: {}", life_support.oxygen_generator_rating);
+            println!("CO2 scrubber rating: {}", life_support.co2_scrubber_rating);
+            println!("Life support rating: {}", life_support.life_support_rating());
+        },
+        Err(e) => {
+            println!("Life support rating failed: {}", e);
+        }
+    }
+}
+
+fn process_input_lines_packed(lines: Vec<String>, tie_policy: TiePolicy) {
+    match packed_diagnostic_grid_from_lines(&lines) {
+        Ok(grid) => {
+            report_power_consumption(calculate_packed_diagnostic_result(&grid, tie_policy));
+        },
+        Err(e) => {
+            report_parse_error(&e);
+        }
+    }
+}
+
+fn report_power_consumption(result: Result<DiagnosticResult, DiagnosticTieError>) {
+    match result {
+        Ok(diagnostic_result) => {
+            if !diagnostic_result.tie_columns.is_empty() {
+                println!("Warning: columns {} were tied", column_list(&diagnostic_result.tie_columns));
+            }
             println!("Power consumption: {}", diagnostic_result.power_consumption());
         },
         Err(e) => {
-            println!("Parse of diagnostic grid failed on line {}, reason {}", e.line_number, e.why);
+            println!("Power consumption failed: {}", e);
         }
     }
+}
+
+fn report_parse_error(e: &DiagnosticParseError) {
+    println!("Parse of diagnostic grid failed on {}", e);
+}
 
+// Columns are listed counting from 1 on the left
+fn column_list(columns: &[usize]) -> String {
+    columns.iter().map(|c| (c + 1).to_string()).collect::<Vec<String>>().join(", ")
 }
@@ -0,0 +1,318 @@
+use crate::binary::{format_bits, Radix};
+use crate::grid::DynamicHeightGrid;
+
+const WORD_BITS: usize = 64;
+// Bit planes in each column counter, so a counter holds up to 2^16 - 1 rows before it must be flushed
+const COUNTER_PLANES: usize = 16;
+
+// A grid of bits with each row packed into 64 bit words, one bit per column. Column c of a row is bit c % 64 of
+// the row's word c / 64, and all rows share one contiguous buffer.
+#[derive(PartialEq, Eq, Clone, Default, Debug)]
+pub struct PackedBitGrid {
+    pub width: usize,
+    words_per_row: usize,
+    rows: usize,
+    words: Vec<u64>
+}
+
+impl PackedBitGrid {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            words_per_row: width.div_ceil(WORD_BITS),
+            rows: 0,
+            words: Vec::new()
+        }
+    }
+
+    pub fn push(&mut self, row: &[bool]) -> Result<(), String> {
+        if row.len() != self.width {
+            return Err(format!("This grid supports rows of length {}, but got a row of length {}", self.width, row.len()));
+        }
+
+        let start = self.words.len();
+        self.words.resize(start + self.words_per_row, 0);
+        for (column, _) in row.iter().enumerate().filter(|(_, &bit)| bit) {
+            self.words[start + column / WORD_BITS] |= 1 << (column % WORD_BITS);
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> bool {
+        assert!(column < self.width, "Column {} is outside a grid of width {}", column, self.width);
+        (self.row_words(row)[column / WORD_BITS] >> (column % WORD_BITS)) & 1 == 1
+    }
+
+    pub fn row_words(&self, row: usize) -> &[u64] {
+        &self.words[row * self.words_per_row..(row + 1) * self.words_per_row]
+    }
+
+    pub fn row(&self, row: usize) -> Vec<bool> {
+        (0..self.width).map(|column| self.get(row, column)).collect()
+    }
+
+    pub fn packed_row(&self, row: usize) -> PackedBits {
+        PackedBits { width: self.width, words: self.row_words(row).to_vec() }
+    }
+
+    // The number of ones in each column. Rows are added into bit-sliced counters, where plane p of a counter word
+    // holds bit p of the count for each of its 64 columns, so every row costs a few word operations per word
+    // rather than one per bit.
+    pub fn column_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.width];
+        let mut counters = vec![[0u64; COUNTER_PLANES]; self.words_per_row];
+        let flush_interval = (1 << COUNTER_PLANES) - 1;
+
+        for row in 0..self.rows {
+            for (&word, planes) in self.row_words(row).iter().zip(counters.iter_mut()) {
+                // Ripple carry addition of one bit to every column's counter at once
+                let mut carry = word;
+                for plane in planes.iter_mut() {
+                    if carry == 0 {
+                        break;
+                    }
+                    let sum = *plane ^ carry;
+                    carry &= *plane;
+                    *plane = sum;
+                }
+            }
+            if (row + 1) % flush_interval == 0 {
+                flush_counters(&mut counters, &mut counts);
+            }
+        }
+        flush_counters(&mut counters, &mut counts);
+
+        counts
+    }
+}
+
+// Adds the counters into the column counts and clears them
+fn flush_counters(counters: &mut [[u64; COUNTER_PLANES]], counts: &mut [usize]) {
+    for (word_index, planes) in counters.iter_mut().enumerate() {
+        for (bit, count) in counts.iter_mut().skip(word_index * WORD_BITS).take(WORD_BITS).enumerate() {
+            *count += planes
+                .iter()
+                .enumerate()
+                .map(|(p, plane)| (((plane >> bit) & 1) as usize) << p)
+                .sum::<usize>();
+        }
+        *planes = [0; COUNTER_PLANES];
+    }
+}
+
+// A single row of bits packed the same way as a row of a PackedBitGrid, with the bit operations of the binary
+// module done a word at a time. Bits beyond the width are always zero.
+#[derive(PartialEq, Eq, Clone, Default, Debug)]
+pub struct PackedBits {
+    width: usize,
+    words: Vec<u64>
+}
+
+impl PackedBits {
+    pub fn from_bits(bits: &[bool]) -> Self {
+        let mut words = vec![0u64; bits.len().div_ceil(WORD_BITS)];
+        for (column, _) in bits.iter().enumerate().filter(|(_, &bit)| bit) {
+            words[column / WORD_BITS] |= 1 << (column % WORD_BITS);
+        }
+        Self { width: bits.len(), words }
+    }
+
+    fn from_words(width: usize, mut words: Vec<u64>) -> Self {
+        if !width.is_multiple_of(WORD_BITS) {
+            if let Some(last) = words.last_mut() {
+                *last &= (1 << (width % WORD_BITS)) - 1;
+            }
+        }
+        Self { width, words }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    pub fn get(&self, column: usize) -> bool {
+        assert!(column < self.width, "Column {} is outside a row of width {}", column, self.width);
+        (self.words[column / WORD_BITS] >> (column % WORD_BITS)) & 1 == 1
+    }
+
+    pub fn to_bits(&self) -> Vec<bool> {
+        (0..self.width).map(|column| self.get(column)).collect()
+    }
+
+    pub fn complement(&self) -> Self {
+        Self::from_words(self.width, self.words.iter().map(|w| !w).collect())
+    }
+
+    // Each column is xored with the column to its left, which sits one bit lower in the words
+    pub fn gray_encode(&self) -> Self {
+        let mut carry = 0;
+        let words = self.words.iter().map(|&w| {
+            let shifted = (w << 1) | carry;
+            carry = w >> (WORD_BITS - 1);
+            w ^ shifted
+        }).collect();
+        Self::from_words(self.width, words)
+    }
+
+    // Each column becomes the xor of itself and every column to its left, a prefix xor within each word carried
+    // into the next by flipping the whole word when the columns so far xor to one
+    pub fn gray_decode(&self) -> Self {
+        let mut carry = 0u64;
+        let words = self.words.iter().map(|&w| {
+            let mut prefix = w;
+            let mut shift = 1;
+            while shift < WORD_BITS {
+                prefix ^= prefix << shift;
+                shift <<= 1;
+            }
+            prefix ^= carry.wrapping_neg();
+            carry = prefix >> (WORD_BITS - 1);
+            prefix
+        }).collect();
+        Self::from_words(self.width, words)
+    }
+
+    pub fn hamming_weight(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    // The number of columns where two rows differ, or None when they have different widths
+    pub fn hamming_distance(&self, other: &PackedBits) -> Option<usize> {
+        if self.width != other.width {
+            return None;
+        }
+        Some(self.words.iter().zip(other.words.iter()).map(|(a, b)| (a ^ b).count_ones() as usize).sum())
+    }
+
+    pub fn format(&self, radix: Radix) -> String {
+        format_bits(&self.to_bits(), radix)
+    }
+}
+
+impl From<&DynamicHeightGrid<bool>> for PackedBitGrid {
+    fn from(grid: &DynamicHeightGrid<bool>) -> Self {
+        let mut packed = PackedBitGrid::new(grid.width);
+        for row in grid.iter() {
+            packed.push(row).expect("Rows of a grid all have its width");
+        }
+        packed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PackedBitGrid;
+    use super::PackedBits;
+    use crate::binary::{complement, gray_decode, gray_encode, hamming_distance, hamming_weight, Radix};
+    use crate::grid::DynamicHeightGrid;
+
+    #[test]
+    fn packed_bit_grid_mismatched_push_is_illegal() {
+        // arrange
+        let mut grid = PackedBitGrid::new(5);
+        // act
+        let result = grid.push(&[true, false]);
+        // assert
+        assert_eq!(Err("This grid supports rows of length 5, but got a row of length 2".to_string()), result);
+        assert!(grid.is_empty());
+    }
+
+    #[test]
+    fn packed_bit_grid_round_trips_rows_across_words() {
+        // arrange
+        let mut grid = PackedBitGrid::new(70);
+        let row: Vec<bool> = (0..70).map(|i| i % 3 == 0 || i == 69).collect();
+        // act
+        grid.push(&[false; 70]).unwrap();
+        grid.push(&row).unwrap();
+        // assert
+        assert_eq!(2, grid.len());
+        assert_eq!(2, grid.row_words(1).len());
+        assert_eq!(row, grid.row(1));
+        assert!(grid.get(1, 69));
+        assert!(!grid.get(0, 69));
+    }
+
+    #[test]
+    fn packed_bit_grid_column_counts_match_unpacked_grid() {
+        // arrange
+        let mut grid = DynamicHeightGrid::<bool>::new(67);
+        for i in 0..200usize {
+            grid.push((0..67).map(|j| (i * 7 + j * 13) % 5 < 2).collect()).unwrap();
+        }
+        // act
+        let result = PackedBitGrid::from(&grid).column_counts();
+        // assert
+        let expected: Vec<usize> = (0..67).map(|j| grid.iter().filter(|row| row[j]).count()).collect();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn packed_bit_grid_column_counts_survive_counter_flushes() {
+        // arrange
+        let mut grid = PackedBitGrid::new(3);
+        for i in 0..70_000 {
+            grid.push(&[true, i % 2 == 0, false]).unwrap();
+        }
+        // act
+        let result = grid.column_counts();
+        // assert
+        assert_eq!(vec![70_000, 35_000, 0], result);
+    }
+
+    #[test]
+    fn packed_bits_match_slice_operations_across_words() {
+        // arrange
+        let a: Vec<bool> = (0..150).map(|i| (i * 7) % 5 < 2).collect();
+        let b: Vec<bool> = (0..150).map(|i| (i * 3) % 4 == 1).collect();
+        // act
+        let packed_a = PackedBits::from_bits(&a);
+        let packed_b = PackedBits::from_bits(&b);
+        // assert
+        assert_eq!(a, packed_a.to_bits());
+        assert_eq!(complement(&a), packed_a.complement().to_bits());
+        assert_eq!(gray_encode(&a), packed_a.gray_encode().to_bits());
+        assert_eq!(gray_decode(&a), packed_a.gray_decode().to_bits());
+        assert_eq!(hamming_weight(&a), packed_a.hamming_weight());
+        assert_eq!(hamming_distance(&a, &b), packed_a.hamming_distance(&packed_b));
+    }
+
+    #[test]
+    fn packed_bits_complement_leaves_padding_clear() {
+        // arrange
+        let bits = PackedBits::from_bits(&[true, false, true]);
+        // act
+        let result = bits.complement();
+        // assert
+        assert_eq!(3, result.width());
+        assert_eq!(&[0b010], result.words());
+        assert_eq!(1, result.hamming_weight());
+        assert_eq!("0x2", result.format(Radix::Hex));
+    }
+
+    #[test]
+    fn packed_grid_rows_support_bit_operations() {
+        // arrange
+        let mut grid = PackedBitGrid::new(5);
+        grid.push(&[true, false, true, true, false]).unwrap();
+        grid.push(&[false, false, true, false, false]).unwrap();
+        // act
+        let result = grid.packed_row(0).hamming_distance(&grid.packed_row(1));
+        // assert
+        assert_eq!(Some(2), result);
+        assert_eq!(None, grid.packed_row(0).hamming_distance(&PackedBits::from_bits(&[true])));
+    }
+}
@@ -0,0 +1,352 @@
+use crate::grid::DynamicHeightGrid;
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+/*
+ * Decoders
+ */
+
+// Turns the text of one cell into its value, or None when the text isn't a valid cell. Depending on how rows are
+// split the text is a single character or a whole token.
+pub trait CellDecoder {
+    type Cell;
+    fn decode(&self, text: &str) -> Option<Self::Cell>;
+}
+
+// '0' and '1', as in the diagnostic report
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BinaryDecoder;
+
+impl CellDecoder for BinaryDecoder {
+    type Cell = bool;
+
+    fn decode(&self, text: &str) -> Option<bool> {
+        match text {
+            "0" => Some(false),
+            "1" => Some(true),
+            _ => None
+        }
+    }
+}
+
+// A single decimal digit, as in height maps
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DigitDecoder;
+
+impl CellDecoder for DigitDecoder {
+    type Cell = u8;
+
+    fn decode(&self, text: &str) -> Option<u8> {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => c.to_digit(10).map(|d| d as u8),
+            _ => None
+        }
+    }
+}
+
+// '#' for a filled cell and '.' for an empty one
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MapDecoder;
+
+impl CellDecoder for MapDecoder {
+    type Cell = bool;
+
+    fn decode(&self, text: &str) -> Option<bool> {
+        match text {
+            "#" => Some(true),
+            "." => Some(false),
+            _ => None
+        }
+    }
+}
+
+// Any type that parses from a token, such as numbers in a whitespace separated grid
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FromStrDecoder<T> {
+    cell: PhantomData<T>
+}
+
+impl<T: FromStr> CellDecoder for FromStrDecoder<T> {
+    type Cell = T;
+
+    fn decode(&self, text: &str) -> Option<T> {
+        text.parse().ok()
+    }
+}
+
+/*
+ * Types
+ */
+
+// How a line is split into cells
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum CellSplit {
+    // One cell per character
+    #[default]
+    Chars,
+    // Cells between each separator character
+    Separator(char),
+    // Tokens between runs of whitespace, ignoring any at either end
+    Whitespace
+}
+
+// What to do with a row whose width differs from the first row's
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub enum RaggedRows<T> {
+    #[default]
+    Error,
+    // Leave the row out of the grid
+    Skip,
+    // Fill out short rows with this cell. Long rows are still an error.
+    Pad(T)
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct GridFormat<T> {
+    pub split: CellSplit,
+    pub ragged: RaggedRows<T>
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum GridParseErrorKind {
+    // A character the decoder doesn't accept, when splitting into characters
+    InvalidChar(char),
+    // A token the decoder doesn't accept, when splitting on separators or whitespace
+    InvalidToken(String),
+    // A row of a different width to the first row. Values are the expected and found widths.
+    RaggedRow(usize, usize)
+}
+
+// Row and column count from 0. For a ragged row the column is where the row stopped matching the first row.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct GridParseError {
+    pub row: usize,
+    pub column: usize,
+    pub kind: GridParseErrorKind
+}
+
+impl fmt::Display for GridParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.row + 1, self.column + 1)?;
+        match &self.kind {
+            GridParseErrorKind::InvalidChar(c) => write!(f, "invalid character '{}'", c),
+            GridParseErrorKind::InvalidToken(token) => write!(f, "invalid cell \"{}\"", token),
+            GridParseErrorKind::RaggedRow(expected, found) => write!(f, "expected {} cells but found {}", expected, found)
+        }
+    }
+}
+
+impl std::error::Error for GridParseError {}
+
+/*
+ * Parsing
+ */
+
+// Decodes every line into a row of the grid, which takes the first row's width
+pub fn parse_grid<D, S>(lines: &[S], decoder: &D, format: &GridFormat<D::Cell>) -> Result<DynamicHeightGrid<D::Cell>, GridParseError>
+where
+    D: CellDecoder,
+    D::Cell: Clone,
+    S: AsRef<str>
+{
+    parse_grid_with_errors(lines, decoder, format, &mut Err)
+}
+
+// Like parse_grid, but leaves out every line that fails and returns their errors alongside the rows that parsed.
+// The grid takes the width of the first line that parses.
+pub fn parse_grid_collecting_errors<D, S>(lines: &[S], decoder: &D, format: &GridFormat<D::Cell>) -> (DynamicHeightGrid<D::Cell>, Vec<GridParseError>)
+where
+    D: CellDecoder,
+    D::Cell: Clone,
+    S: AsRef<str>
+{
+    let mut errors = Vec::new();
+    let grid = parse_grid_with_errors(lines, decoder, format, &mut |e| {
+        errors.push(e);
+        Ok(())
+    }).expect("Collecting errors never stops the parse");
+    (grid, errors)
+}
+
+// Each failing line is passed to on_error, which either stops the parse by returning the error or lets it carry on
+// without that line
+fn parse_grid_with_errors<D, S, E>(lines: &[S], decoder: &D, format: &GridFormat<D::Cell>, on_error: &mut E) -> Result<DynamicHeightGrid<D::Cell>, GridParseError>
+where
+    D: CellDecoder,
+    D::Cell: Clone,
+    S: AsRef<str>,
+    E: FnMut(GridParseError) -> Result<(), GridParseError>
+{
+    let mut grid: Option<DynamicHeightGrid<D::Cell>> = None;
+
+    for (row, line) in lines.iter().enumerate() {
+        let mut cells = match parse_row(row, line.as_ref(), decoder, format.split) {
+            Ok(cells) => cells,
+            Err(e) => {
+                on_error(e)?;
+                continue;
+            }
+        };
+        let grid = grid.get_or_insert_with(|| DynamicHeightGrid::new(cells.len()));
+        if cells.len() != grid.width {
+            let ragged = GridParseError {
+                row,
+                column: cells.len().min(grid.width),
+                kind: GridParseErrorKind::RaggedRow(grid.width, cells.len())
+            };
+            match &format.ragged {
+                RaggedRows::Skip => continue,
+                RaggedRows::Pad(fill) if cells.len() < grid.width => cells.resize(grid.width, fill.clone()),
+                _ => {
+                    on_error(ragged)?;
+                    continue;
+                }
+            }
+        }
+        grid.push(cells).expect("Row has the grid's width");
+    }
+
+    Ok(grid.unwrap_or_else(|| DynamicHeightGrid::new(0)))
+}
+
+// Decodes a single line, giving the error its row number
+pub fn parse_row<D: CellDecoder>(row: usize, line: &str, decoder: &D, split: CellSplit) -> Result<Vec<D::Cell>, GridParseError> {
+    let invalid = |column: usize, kind: GridParseErrorKind| GridParseError { row, column, kind };
+    match split {
+        CellSplit::Chars => line
+            .chars()
+            .enumerate()
+            .map(|(column, c)| {
+                let mut buffer = [0; 4];
+                decoder.decode(c.encode_utf8(&mut buffer)).ok_or_else(|| invalid(column, GridParseErrorKind::InvalidChar(c)))
+            })
+            .collect(),
+        CellSplit::Separator(separator) => decode_tokens(line.split(separator), decoder, invalid),
+        CellSplit::Whitespace => decode_tokens(line.split_whitespace(), decoder, invalid)
+    }
+}
+
+fn decode_tokens<'a, D, I, E>(tokens: I, decoder: &D, invalid: E) -> Result<Vec<D::Cell>, GridParseError>
+where
+    D: CellDecoder,
+    I: Iterator<Item = &'a str>,
+    E: Fn(usize, GridParseErrorKind) -> GridParseError
+{
+    tokens
+        .enumerate()
+        .map(|(column, token)| decoder.decode(token).ok_or_else(|| invalid(column, GridParseErrorKind::InvalidToken(token.to_string()))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_grid;
+    use super::parse_grid_collecting_errors;
+    use super::BinaryDecoder;
+    use super::CellSplit;
+    use super::DigitDecoder;
+    use super::FromStrDecoder;
+    use super::GridFormat;
+    use super::GridParseError;
+    use super::GridParseErrorKind;
+    use super::MapDecoder;
+    use super::RaggedRows;
+
+    #[test]
+    fn parse_grid_decodes_each_kind_of_grid() {
+        // arrange
+        let binary = ["101", "010"];
+        let digits = ["219", "398"];
+        let map = ["#.", ".#"];
+        // act
+        let binary_grid = parse_grid(&binary, &BinaryDecoder, &GridFormat::default()).unwrap();
+        let digit_grid = parse_grid(&digits, &DigitDecoder, &GridFormat::default()).unwrap();
+        let map_grid = parse_grid(&map, &MapDecoder, &GridFormat::default()).unwrap();
+        // assert
+        assert_eq!(vec![true, false, true], binary_grid[0]);
+        assert_eq!(vec![3, 9, 8], digit_grid[1]);
+        assert_eq!(vec![false, true], map_grid[1]);
+    }
+
+    #[test]
+    fn parse_grid_splits_on_whitespace_and_separators() {
+        // arrange
+        let board = ["22 13  17", " 8  2 23"];
+        let csv = ["1,0,1"];
+        let decoder = FromStrDecoder::<i32>::default();
+        // act
+        let board_grid = parse_grid(&board, &decoder, &GridFormat { split: CellSplit::Whitespace, ..Default::default() }).unwrap();
+        let csv_grid = parse_grid(&csv, &BinaryDecoder, &GridFormat { split: CellSplit::Separator(','), ..Default::default() }).unwrap();
+        // assert
+        assert_eq!(3, board_grid.width);
+        assert_eq!(vec![8, 2, 23], board_grid[1]);
+        assert_eq!(vec![true, false, true], csv_grid[0]);
+    }
+
+    #[test]
+    fn parse_grid_reports_position_of_invalid_cell() {
+        // arrange
+        let lines = ["101", "1x1"];
+        let tokens = ["1 2", "3 four"];
+        // act
+        let result = parse_grid(&lines, &BinaryDecoder, &GridFormat::default());
+        let token_result = parse_grid(&tokens, &FromStrDecoder::<u8>::default(), &GridFormat { split: CellSplit::Whitespace, ..Default::default() });
+        // assert
+        assert_eq!(Err(GridParseError { row: 1, column: 1, kind: GridParseErrorKind::InvalidChar('x') }), result);
+        assert_eq!("line 2, column 2: invalid cell \"four\"", token_result.unwrap_err().to_string());
+    }
+
+    #[test]
+    fn parse_grid_applies_ragged_row_policy() {
+        // arrange
+        let lines = ["123", "45", "678"];
+        // act
+        let error = parse_grid(&lines, &DigitDecoder, &GridFormat::default());
+        let skipped = parse_grid(&lines, &DigitDecoder, &GridFormat { ragged: RaggedRows::Skip, ..Default::default() }).unwrap();
+        let padded = parse_grid(&lines, &DigitDecoder, &GridFormat { ragged: RaggedRows::Pad(0), ..Default::default() }).unwrap();
+        // assert
+        assert_eq!(Err(GridParseError { row: 1, column: 2, kind: GridParseErrorKind::RaggedRow(3, 2) }), error);
+        assert_eq!(2, skipped.len());
+        assert_eq!(vec![6, 7, 8], skipped[1]);
+        assert_eq!(vec![4, 5, 0], padded[1]);
+    }
+
+    #[test]
+    fn parse_grid_padding_still_rejects_long_rows() {
+        // arrange
+        let lines = ["12", "345"];
+        // act
+        let result = parse_grid(&lines, &DigitDecoder, &GridFormat { ragged: RaggedRows::Pad(0), ..Default::default() });
+        // assert
+        assert_eq!(Err(GridParseError { row: 1, column: 2, kind: GridParseErrorKind::RaggedRow(2, 3) }), result);
+    }
+
+    #[test]
+    fn parse_grid_of_no_lines_is_empty() {
+        // arrange
+        let lines: [&str; 0] = [];
+        // act
+        let result = parse_grid(&lines, &BinaryDecoder, &GridFormat::default()).unwrap();
+        // assert
+        assert!(result.is_empty());
+        assert_eq!(0, result.width);
+    }
+
+    #[test]
+    fn parse_grid_collecting_errors_keeps_good_rows() {
+        // arrange
+        let lines = ["1x3", "456", "78", "901"];
+        // act
+        let (grid, errors) = parse_grid_collecting_errors(&lines, &DigitDecoder, &GridFormat::default());
+        // assert
+        assert_eq!(3, grid.width);
+        assert_eq!(2, grid.len());
+        assert_eq!(vec![9, 0, 1], grid[1]);
+        assert_eq!(vec![
+            GridParseError { row: 0, column: 1, kind: GridParseErrorKind::InvalidChar('x') },
+            GridParseError { row: 2, column: 2, kind: GridParseErrorKind::RaggedRow(3, 2) }
+        ], errors);
+    }
+}
@@ -0,0 +1,384 @@
+use crate::grid::DynamicHeightGrid;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+// A cell position as (row, column)
+pub type Coord = (usize, usize);
+
+/*
+ * Types
+ */
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Neighbourhood {
+    // Up, down, left and right
+    Four,
+    // Diagonals as well
+    Eight
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum Edges {
+    // Cells on an edge have fewer neighbours
+    #[default]
+    Bounded,
+    // Stepping off one edge comes back on at the opposite one
+    Wrapping
+}
+
+// How cells are connected to each other, shared by all the searches
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Adjacency {
+    pub neighbourhood: Neighbourhood,
+    pub edges: Edges
+}
+
+impl Adjacency {
+    pub fn new(neighbourhood: Neighbourhood, edges: Edges) -> Self {
+        Self { neighbourhood, edges }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Path {
+    // Cells from the start to the goal, both included
+    pub cells: Vec<Coord>,
+    pub cost: u64
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Components {
+    // The component index of each cell
+    pub labels: DynamicHeightGrid<usize>,
+    // The cells of each component, in the order they were reached
+    pub regions: Vec<Vec<Coord>>
+}
+
+const FOUR_OFFSETS: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+const EIGHT_OFFSETS: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+/*
+ * Neighbours
+ */
+
+// The neighbours of a cell in reading order. On a wrapping grid too small for all the offsets to be distinct, each
+// neighbour appears once and a cell is never its own neighbour.
+pub fn neighbours<T>(grid: &DynamicHeightGrid<T>, (row, column): Coord, adjacency: Adjacency) -> Vec<Coord> {
+    let height = grid.len() as isize;
+    let width = grid.width as isize;
+    let offsets: &[(isize, isize)] = match adjacency.neighbourhood {
+        Neighbourhood::Four => &FOUR_OFFSETS,
+        Neighbourhood::Eight => &EIGHT_OFFSETS
+    };
+
+    let mut result = Vec::with_capacity(offsets.len());
+    for (dr, dc) in offsets {
+        let (r, c) = (row as isize + dr, column as isize + dc);
+        let neighbour = match adjacency.edges {
+            Edges::Bounded if r < 0 || c < 0 || r >= height || c >= width => continue,
+            Edges::Bounded => (r as usize, c as usize),
+            Edges::Wrapping => (r.rem_euclid(height) as usize, c.rem_euclid(width) as usize)
+        };
+        if neighbour != (row, column) && !result.contains(&neighbour) {
+            result.push(neighbour);
+        }
+    }
+    result
+}
+
+/*
+ * Searches
+ */
+
+// Fewest steps from start to goal, only stepping onto cells that are passable. The start itself need not be.
+// None if there's no such path, including when start or goal is outside the grid.
+pub fn bfs_path<T, F>(grid: &DynamicHeightGrid<T>, start: Coord, goal: Coord, adjacency: Adjacency, mut passable: F) -> Option<Path>
+where
+    F: FnMut(&T) -> bool
+{
+    if !in_grid(grid, start) || !in_grid(grid, goal) {
+        return None;
+    }
+    let mut previous: DynamicHeightGrid<Option<Coord>> = grid.map(|_| None);
+    let mut queue = VecDeque::from([start]);
+    previous[start] = Some(start);
+
+    while let Some(current) = queue.pop_front() {
+        if current == goal {
+            let cells = trace_path(&previous, start, goal);
+            return Some(Path { cost: cells.len() as u64 - 1, cells });
+        }
+        for next in neighbours(grid, current, adjacency) {
+            if previous[next].is_none() && passable(&grid[next]) {
+                previous[next] = Some(current);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+// Cheapest path by Dijkstra's algorithm. The cost of stepping between two cells is given by step_cost, with None
+// meaning the step can't be taken.
+pub fn dijkstra_path<T, F>(grid: &DynamicHeightGrid<T>, start: Coord, goal: Coord, adjacency: Adjacency, step_cost: F) -> Option<Path>
+where
+    F: FnMut(&T, &T) -> Option<u64>
+{
+    a_star_path(grid, start, goal, adjacency, step_cost, |_| 0)
+}
+
+// Cheapest path by A*, guided by a heuristic estimate of the cost from a cell to the goal. The heuristic must never
+// overestimate, or the path found may not be the cheapest. Like bfs_path, None when start or goal is off the grid.
+pub fn a_star_path<T, F, H>(grid: &DynamicHeightGrid<T>, start: Coord, goal: Coord, adjacency: Adjacency, mut step_cost: F, mut heuristic: H) -> Option<Path>
+where
+    F: FnMut(&T, &T) -> Option<u64>,
+    H: FnMut(Coord) -> u64
+{
+    if !in_grid(grid, start) || !in_grid(grid, goal) {
+        return None;
+    }
+    let mut best: DynamicHeightGrid<Option<u64>> = grid.map(|_| None);
+    let mut previous: DynamicHeightGrid<Option<Coord>> = grid.map(|_| None);
+    let mut open = BinaryHeap::new();
+    best[start] = Some(0);
+    previous[start] = Some(start);
+    open.push(Reverse((heuristic(start), 0, start)));
+
+    while let Some(Reverse((_, cost, current))) = open.pop() {
+        if current == goal {
+            return Some(Path { cells: trace_path(&previous, start, goal), cost });
+        }
+        // A cheaper route to this cell was found after this entry was queued
+        if best[current].is_some_and(|b| b < cost) {
+            continue;
+        }
+        for next in neighbours(grid, current, adjacency) {
+            let Some(step) = step_cost(&grid[current], &grid[next]) else {
+                continue;
+            };
+            let next_cost = cost + step;
+            if best[next].is_none_or(|b| next_cost < b) {
+                best[next] = Some(next_cost);
+                previous[next] = Some(current);
+                open.push(Reverse((next_cost + heuristic(next), next_cost, next)));
+            }
+        }
+    }
+    None
+}
+
+fn in_grid<T>(grid: &DynamicHeightGrid<T>, (row, column): Coord) -> bool {
+    grid.get(row, column).is_some()
+}
+
+// Follows the previous cell links back from the goal
+fn trace_path(previous: &DynamicHeightGrid<Option<Coord>>, start: Coord, goal: Coord) -> Vec<Coord> {
+    let mut cells = vec![goal];
+    let mut current = goal;
+    while current != start {
+        current = previous[current].expect("Every reached cell links back towards the start");
+        cells.push(current);
+    }
+    cells.reverse();
+    cells
+}
+
+/*
+ * Regions
+ */
+
+// Every cell reachable from start by steps between connected cells, in the order they were reached. Empty when the
+// start is outside the grid.
+pub fn flood_fill<T, F>(grid: &DynamicHeightGrid<T>, start: Coord, adjacency: Adjacency, mut connected: F) -> Vec<Coord>
+where
+    F: FnMut(&T, &T) -> bool
+{
+    if !in_grid(grid, start) {
+        return Vec::new();
+    }
+    let mut visited = grid.map(|_| false);
+    fill_from(grid, start, adjacency, &mut connected, &mut visited)
+}
+
+// Splits the grid into regions of connected cells, numbered in reading order of their first cell
+pub fn connected_components<T, F>(grid: &DynamicHeightGrid<T>, adjacency: Adjacency, mut connected: F) -> Components
+where
+    F: FnMut(&T, &T) -> bool
+{
+    let mut visited = grid.map(|_| false);
+    let mut labels = grid.map(|_| 0);
+    let mut regions = Vec::new();
+
+    for (at, _) in grid.enumerate() {
+        if visited[at] {
+            continue;
+        }
+        let region = fill_from(grid, at, adjacency, &mut connected, &mut visited);
+        for &cell in &region {
+            labels[cell] = regions.len();
+        }
+        regions.push(region);
+    }
+
+    Components { labels, regions }
+}
+
+fn fill_from<T, F>(grid: &DynamicHeightGrid<T>, start: Coord, adjacency: Adjacency, connected: &mut F, visited: &mut DynamicHeightGrid<bool>) -> Vec<Coord>
+where
+    F: FnMut(&T, &T) -> bool
+{
+    let mut region = vec![start];
+    let mut queue = VecDeque::from([start]);
+    visited[start] = true;
+
+    while let Some(current) = queue.pop_front() {
+        for next in neighbours(grid, current, adjacency) {
+            if !visited[next] && connected(&grid[current], &grid[next]) {
+                visited[next] = true;
+                region.push(next);
+                queue.push_back(next);
+            }
+        }
+    }
+    region
+}
+
+// Cells lower than every one of their neighbours, in reading order
+pub fn local_minima<T: Ord>(grid: &DynamicHeightGrid<T>, adjacency: Adjacency) -> Vec<Coord> {
+    grid.enumerate()
+        .filter(|&(at, cell)| neighbours(grid, at, adjacency).iter().all(|&n| *cell < grid[n]))
+        .map(|(at, _)| at)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::a_star_path;
+    use super::bfs_path;
+    use super::connected_components;
+    use super::dijkstra_path;
+    use super::flood_fill;
+    use super::local_minima;
+    use super::neighbours;
+    use super::Adjacency;
+    use super::Edges;
+    use super::Neighbourhood;
+    use crate::grid::DynamicHeightGrid;
+
+    const FOUR: Adjacency = Adjacency { neighbourhood: Neighbourhood::Four, edges: Edges::Bounded };
+
+    fn digit_grid(lines: &[&str]) -> DynamicHeightGrid<u32> {
+        lines.iter().map(|line| line.chars().map(|c| c.to_digit(10).unwrap()).collect()).collect()
+    }
+
+    #[test]
+    fn neighbours_are_bounded_at_edges() {
+        // arrange
+        let grid = digit_grid(&["123", "456", "789"]);
+        // act
+        let corner = neighbours(&grid, (0, 0), FOUR);
+        let centre = neighbours(&grid, (1, 1), Adjacency::new(Neighbourhood::Eight, Edges::Bounded));
+        // assert
+        assert_eq!(vec![(0, 1), (1, 0)], corner);
+        assert_eq!(8, centre.len());
+    }
+
+    #[test]
+    fn neighbours_wrap_around_edges() {
+        // arrange
+        let grid = digit_grid(&["123", "456", "789"]);
+        // act
+        let corner = neighbours(&grid, (0, 0), Adjacency::new(Neighbourhood::Four, Edges::Wrapping));
+        let narrow = neighbours(&digit_grid(&["12"]), (0, 0), Adjacency::new(Neighbourhood::Eight, Edges::Wrapping));
+        // assert
+        assert_eq!(vec![(2, 0), (0, 2), (0, 1), (1, 0)], corner);
+        assert_eq!(vec![(0, 1)], narrow);
+    }
+
+    #[test]
+    fn bfs_path_avoids_walls() {
+        // arrange
+        let grid = digit_grid(&["000", "110", "000"]);
+        // act
+        let result = bfs_path(&grid, (0, 0), (2, 0), FOUR, |&c| c == 0).unwrap();
+        // assert
+        assert_eq!(6, result.cost);
+        assert_eq!(vec![(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (2, 1), (2, 0)], result.cells);
+        assert_eq!(None, bfs_path(&grid, (0, 0), (1, 0), FOUR, |&c| c == 0));
+    }
+
+    #[test]
+    fn paths_from_or_to_outside_the_grid_are_none() {
+        // arrange
+        let grid = digit_grid(&["000", "000"]);
+        // act
+        let from_outside = bfs_path(&grid, (2, 0), (0, 0), FOUR, |&c| c == 0);
+        let to_outside = a_star_path(&grid, (0, 0), (0, 3), FOUR, |_, _| Some(1), |_| 0);
+        let fill_outside = flood_fill(&grid, (5, 5), FOUR, |_, _| true);
+        // assert
+        assert_eq!(None, from_outside);
+        assert_eq!(None, to_outside);
+        assert!(fill_outside.is_empty());
+    }
+
+    #[test]
+    fn dijkstra_path_finds_lowest_risk() {
+        // arrange
+        let grid = digit_grid(&["1163751742", "1381373672", "2136511328", "3694931569", "7463417111",
+                                "1319128137", "1359912421", "3125421639", "1293138521", "2311944581"]);
+        // act
+        let result = dijkstra_path(&grid, (0, 0), (9, 9), FOUR, |_, &to| Some(to as u64)).unwrap();
+        // assert
+        assert_eq!(40, result.cost);
+        assert_eq!((0, 0), result.cells[0]);
+        assert_eq!((9, 9), *result.cells.last().unwrap());
+        assert_eq!(40, result.cells[1..].iter().map(|&at| grid[at] as u64).sum::<u64>());
+    }
+
+    #[test]
+    fn a_star_path_matches_dijkstra_with_manhattan_heuristic() {
+        // arrange
+        let grid = digit_grid(&["19111", "19191", "11191", "99991", "11111"]);
+        let goal = (4, 0);
+        let manhattan = |(r, c): (usize, usize)| (r.abs_diff(goal.0) + c.abs_diff(goal.1)) as u64;
+        // act
+        let result = a_star_path(&grid, (0, 0), goal, FOUR, |_, &to| Some(to as u64), manhattan).unwrap();
+        // assert
+        assert_eq!(dijkstra_path(&grid, (0, 0), goal, FOUR, |_, &to| Some(to as u64)).unwrap().cost, result.cost);
+        assert_eq!(12, result.cost);
+    }
+
+    #[test]
+    fn flood_fill_stays_within_region() {
+        // arrange
+        let grid = digit_grid(&["2199943210", "3987894921", "9856789892", "8767896789", "9899965678"]);
+        // act
+        let result = flood_fill(&grid, (0, 0), FOUR, |_, &to| to != 9);
+        // assert
+        assert_eq!(3, result.len());
+        assert_eq!((0, 0), result[0]);
+    }
+
+    #[test]
+    fn connected_components_label_every_cell() {
+        // arrange
+        let grid = digit_grid(&["110", "010", "001"]);
+        // act
+        let result = connected_components(&grid, FOUR, |a, b| a == b);
+        // assert
+        assert_eq!(4, result.regions.len());
+        assert_eq!(vec![(0, 0), (0, 1), (1, 1)], result.regions[0]);
+        assert_eq!(vec![0, 0, 1], result.labels[0]);
+        assert_eq!(vec![2, 0, 1], result.labels[1]);
+        assert_eq!(vec![2, 2, 3], result.labels[2]);
+    }
+
+    #[test]
+    fn local_minima_are_strictly_lowest() {
+        // arrange
+        let grid = digit_grid(&["2199943210", "3987894921", "9856789892", "8767896789", "9899965678"]);
+        // act
+        let result = local_minima(&grid, FOUR);
+        // assert
+        assert_eq!(vec![(0, 1), (0, 9), (2, 2), (4, 6)], result);
+    }
+}
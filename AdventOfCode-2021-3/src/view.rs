@@ -0,0 +1,199 @@
+use crate::grid::DynamicHeightGrid;
+use std::ops::Index;
+
+// A read only view of a grid that is transposed, rotated or flipped by changing how coordinates are looked up
+// rather than by moving any cells. Views can be turned again, since every combination of these is a reversal of
+// the rows and/or columns followed by an optional swap of rows for columns.
+#[derive(Debug)]
+pub struct GridView<'a, T> {
+    grid: &'a DynamicHeightGrid<T>,
+    // Whether view rows are grid columns
+    swap: bool,
+    // Reversals applied in view coordinates, before any swap
+    reverse_rows: bool,
+    reverse_columns: bool
+}
+
+// Derived Clone and Copy would needlessly need T to be Clone and Copy
+impl<T> Clone for GridView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for GridView<'_, T> {}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Rotation {
+    // Clockwise
+    Quarter,
+    Half,
+    ThreeQuarters
+}
+
+impl<'a, T> GridView<'a, T> {
+    pub fn new(grid: &'a DynamicHeightGrid<T>) -> Self {
+        Self { grid, swap: false, reverse_rows: false, reverse_columns: false }
+    }
+
+    pub fn width(&self) -> usize {
+        if self.swap { self.grid.len() } else { self.grid.width }
+    }
+
+    pub fn len(&self) -> usize {
+        if self.swap { self.grid.width } else { self.grid.len() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn transpose(self) -> Self {
+        Self {
+            swap: !self.swap,
+            reverse_rows: self.reverse_columns,
+            reverse_columns: self.reverse_rows,
+            ..self
+        }
+    }
+
+    // Mirrors left to right
+    pub fn flip_horizontal(self) -> Self {
+        Self { reverse_columns: !self.reverse_columns, ..self }
+    }
+
+    // Mirrors top to bottom
+    pub fn flip_vertical(self) -> Self {
+        Self { reverse_rows: !self.reverse_rows, ..self }
+    }
+
+    pub fn rotate(self, rotation: Rotation) -> Self {
+        match rotation {
+            Rotation::Quarter => self.transpose().flip_horizontal(),
+            Rotation::Half => self.flip_horizontal().flip_vertical(),
+            Rotation::ThreeQuarters => self.transpose().flip_vertical()
+        }
+    }
+
+    // The grid coordinates of a view cell, or None outside the view
+    fn source(&self, row: usize, column: usize) -> Option<(usize, usize)> {
+        if row >= self.len() || column >= self.width() {
+            return None;
+        }
+        let row = if self.reverse_rows { self.len() - 1 - row } else { row };
+        let column = if self.reverse_columns { self.width() - 1 - column } else { column };
+        Some(if self.swap { (column, row) } else { (row, column) })
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Option<&'a T> {
+        let grid = self.grid;
+        self.source(row, column).map(|at| &grid[at])
+    }
+
+    pub fn row(&self, row: usize) -> impl Iterator<Item = &'a T> {
+        assert!(row < self.len(), "Row {} is outside a view of {} rows", row, self.len());
+        let view = *self;
+        (0..self.width()).map(move |column| view.get(row, column).expect("Column is within the view"))
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = &'a T>> {
+        let view = *self;
+        (0..self.len()).map(move |row| view.row(row))
+    }
+
+    pub fn column(&self, column: usize) -> impl Iterator<Item = &'a T> {
+        self.transpose().row(column)
+    }
+
+    // Copies the view out into a grid of its own
+    pub fn to_grid(&self) -> DynamicHeightGrid<T> where T: Clone {
+        self.rows().map(|row| row.cloned().collect()).collect::<DynamicHeightGrid<T>>()
+    }
+}
+
+impl<T> Index<(usize, usize)> for GridView<'_, T> {
+    type Output = T;
+
+    fn index(&self, (row, column): (usize, usize)) -> &Self::Output {
+        self.get(row, column).unwrap_or_else(|| panic!("Cell ({}, {}) is outside a view of {} by {}", row, column, self.len(), self.width()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rotation;
+    use crate::grid::DynamicHeightGrid;
+    use crate::grid::Transpose;
+
+    fn sample_grid() -> DynamicHeightGrid<i32> {
+        vec![vec![1, 2, 3], vec![4, 5, 6]].into_iter().collect()
+    }
+
+    fn rows_of(grid: &DynamicHeightGrid<i32>) -> Vec<Vec<i32>> {
+        grid.iter().cloned().collect()
+    }
+
+    #[test]
+    fn transposed_view_matches_transpose() {
+        // arrange
+        let grid = sample_grid();
+        // act
+        let view = grid.view().transpose();
+        // assert
+        assert_eq!(3, view.len());
+        assert_eq!(2, view.width());
+        assert_eq!(grid.transpose(), view.to_grid());
+        assert_eq!(6, view[(2, 1)]);
+        assert_eq!(None, view.get(0, 2));
+    }
+
+    #[test]
+    fn rotated_views_turn_clockwise() {
+        // arrange
+        let grid = sample_grid();
+        // act
+        let quarter = grid.view().rotate(Rotation::Quarter).to_grid();
+        let half = grid.view().rotate(Rotation::Half).to_grid();
+        let three_quarters = grid.view().rotate(Rotation::ThreeQuarters).to_grid();
+        // assert
+        assert_eq!(vec![vec![4, 1], vec![5, 2], vec![6, 3]], rows_of(&quarter));
+        assert_eq!(vec![vec![6, 5, 4], vec![3, 2, 1]], rows_of(&half));
+        assert_eq!(vec![vec![3, 6], vec![2, 5], vec![1, 4]], rows_of(&three_quarters));
+    }
+
+    #[test]
+    fn flipped_views_mirror() {
+        // arrange
+        let grid = sample_grid();
+        // act
+        let horizontal = grid.view().flip_horizontal().to_grid();
+        let vertical = grid.view().flip_vertical().to_grid();
+        // assert
+        assert_eq!(vec![vec![3, 2, 1], vec![6, 5, 4]], rows_of(&horizontal));
+        assert_eq!(vec![vec![4, 5, 6], vec![1, 2, 3]], rows_of(&vertical));
+    }
+
+    #[test]
+    fn views_compose() {
+        // arrange
+        let grid = sample_grid();
+        // act
+        let full_turn = grid.view().rotate(Rotation::Quarter).rotate(Rotation::ThreeQuarters);
+        let two_quarters = grid.view().rotate(Rotation::Quarter).rotate(Rotation::Quarter);
+        let turned_transpose = grid.view().rotate(Rotation::Quarter).transpose().to_grid();
+        // assert
+        assert_eq!(grid, full_turn.to_grid());
+        assert_eq!(grid.view().rotate(Rotation::Half).to_grid(), two_quarters.to_grid());
+        assert_eq!(grid.view().flip_vertical().to_grid(), turned_transpose);
+    }
+
+    #[test]
+    fn view_columns_follow_the_view() {
+        // arrange
+        let grid = sample_grid();
+        // act
+        let result: Vec<i32> = grid.view().rotate(Rotation::Quarter).column(0).copied().collect();
+        // assert
+        assert_eq!(vec![4, 5, 6], result);
+    }
+}
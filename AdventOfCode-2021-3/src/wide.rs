@@ -0,0 +1,153 @@
+use std::fmt;
+
+const DECIMAL_CHUNK: u64 = 10_000_000_000_000_000_000;
+
+// An unsigned integer of any size, for diagnostics with rows too long for the built in integer types.
+// Stored as 64 bit words, least significant first, with no high zero words so equal numbers compare equal.
+#[derive(PartialEq, Eq, Clone, Default, Debug, Hash)]
+pub struct WideNumber {
+    words: Vec<u64>
+}
+
+impl WideNumber {
+    // Reads bits most significant first, as written in a diagnostic row
+    pub fn from_bits(bits: &[bool]) -> Self {
+        let mut words = vec![0u64; bits.len().div_ceil(64)];
+        for (i, _) in bits.iter().rev().enumerate().filter(|(_, &bit)| bit) {
+            words[i / 64] |= 1 << (i % 64);
+        }
+        Self::from_words(words)
+    }
+
+    fn from_words(mut words: Vec<u64>) -> Self {
+        while words.last() == Some(&0) {
+            words.pop();
+        }
+        Self { words }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    // The number of bits needed to write the number, with none needed for zero
+    pub fn bit_len(&self) -> usize {
+        match self.words.last() {
+            Some(top) => self.words.len() * 64 - top.leading_zeros() as usize,
+            None => 0
+        }
+    }
+
+    pub fn to_u128(&self) -> Option<u128> {
+        match self.words[..] {
+            [] => Some(0),
+            [low] => Some(low as u128),
+            [low, high] => Some(((high as u128) << 64) | low as u128),
+            _ => None
+        }
+    }
+
+    pub fn mul(&self, other: &WideNumber) -> WideNumber {
+        let mut words = vec![0u64; self.words.len() + other.words.len()];
+        for (i, &a) in self.words.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, &b) in other.words.iter().enumerate() {
+                let product = a as u128 * b as u128 + words[i + j] as u128 + carry;
+                words[i + j] = product as u64;
+                carry = product >> 64;
+            }
+            words[i + other.words.len()] = carry as u64;
+        }
+        Self::from_words(words)
+    }
+
+    // Divides in place by a single word, returning the remainder
+    fn div_rem_word(&mut self, divisor: u64) -> u64 {
+        let mut remainder = 0u128;
+        for word in self.words.iter_mut().rev() {
+            let current = (remainder << 64) | *word as u128;
+            *word = (current / divisor as u128) as u64;
+            remainder = current % divisor as u128;
+        }
+        *self = Self::from_words(std::mem::take(&mut self.words));
+        remainder as u64
+    }
+}
+
+impl From<u64> for WideNumber {
+    fn from(n: u64) -> Self {
+        Self::from_words(vec![n])
+    }
+}
+
+impl From<u128> for WideNumber {
+    fn from(n: u128) -> Self {
+        Self::from_words(vec![n as u64, (n >> 64) as u64])
+    }
+}
+
+// Written in decimal, taking 19 digits at a time
+impl fmt::Display for WideNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut remaining = self.clone();
+        let mut chunks = Vec::new();
+        loop {
+            chunks.push(remaining.div_rem_word(DECIMAL_CHUNK));
+            if remaining.is_zero() {
+                break;
+            }
+        }
+
+        let mut chunks = chunks.iter().rev();
+        write!(f, "{}", chunks.next().expect("There is always at least one chunk"))?;
+        for chunk in chunks {
+            write!(f, "{:019}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WideNumber;
+
+    #[test]
+    fn wide_number_from_bits_reads_most_significant_first() {
+        // arrange
+        let bits = vec![false, true, false, false, true];
+        // act
+        let result = WideNumber::from_bits(&bits);
+        // assert
+        assert_eq!(WideNumber::from(9u64), result);
+        assert_eq!(4, result.bit_len());
+        assert_eq!(WideNumber::default(), WideNumber::from_bits(&[false, false]));
+    }
+
+    #[test]
+    fn wide_number_displays_beyond_128_bits() {
+        // arrange
+        let bits = vec![true; 130];
+        // act
+        let result = WideNumber::from_bits(&bits).to_string();
+        // assert
+        assert_eq!("1361129467683753853853498429727072845823", result);
+    }
+
+    #[test]
+    fn wide_number_mul_carries_across_words() {
+        // arrange
+        let a = WideNumber::from(u64::MAX);
+        let b = WideNumber::from(1u128 << 64);
+        // act
+        let result = a.mul(&b);
+        // assert
+        assert_eq!(Some((u64::MAX as u128) << 64), result.to_u128());
+        assert_eq!("340282366920938463444927863358058659840", result.to_string());
+        assert_eq!(None, result.mul(&b).to_u128());
+    }
+
+    #[test]
+    fn wide_number_zero_displays_as_zero() {
+        assert_eq!("0", WideNumber::default().to_string());
+    }
+}